@@ -0,0 +1,64 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, SaltString},
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+};
+use config::{AccountsConfig, InviteRole};
+use entity::entities::{invite_code, sea_orm_active_enums::Role};
+use sea_orm::{sea_query::OnConflict, DatabaseConnection, EntityTrait, Set};
+
+/// Add the invite codes from the config to the database. A code that's
+/// already there keeps however many uses it has left, but gets the role the
+/// config gives it now.
+pub async fn update_database(accounts_config: &AccountsConfig, db: &DatabaseConnection) {
+    if accounts_config.invite_codes.is_empty() {
+        return;
+    }
+
+    invite_code::Entity::insert_many(accounts_config.invite_codes.iter().map(|invite_code| {
+        invite_code::ActiveModel {
+            code: Set(invite_code.code.clone()),
+            uses_left: Set(invite_code.uses),
+            role: Set(match invite_code.role {
+                InviteRole::Hacker => Role::Hacker,
+                InviteRole::Organizer => Role::Organizer,
+                InviteRole::Admin => Role::Admin,
+            }),
+        }
+    }))
+    .on_conflict(
+        OnConflict::column(invite_code::Column::Code)
+            .update_column(invite_code::Column::Role)
+            .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await
+    .expect("Failed to add invite codes");
+}
+
+/// Hash a password to be stored. Argon2 is slow on purpose, so this runs on a
+/// thread where it won't hold up every other session.
+pub async fn hash_password(password: String) -> String {
+    actix_web::web::block(move || {
+        let salt = SaltString::generate(&mut OsRng);
+
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .expect("Failed to hash password")
+            .to_string()
+    })
+    .await
+    .expect("Failed to hash password")
+}
+
+/// Check a password against a stored hash
+pub async fn verify_password(password: String, password_hash: String) -> bool {
+    actix_web::web::block(move || {
+        PasswordHash::new(&password_hash)
+            .and_then(|password_hash| {
+                Argon2::default().verify_password(password.as_bytes(), &password_hash)
+            })
+            .is_ok()
+    })
+    .await
+    .expect("Failed to verify password")
+}
@@ -0,0 +1,184 @@
+//! HTTP API for challenge tooling, such as scripts that provision a separate
+//! instance of a challenge for every team. Every request needs the configured
+//! API token, sent as `Authorization: Bearer <token>`. Challenges are known by
+//! their id, which stays the same when a challenge is renamed.
+
+use actix_web::{
+    get,
+    http::header,
+    web::{Data, Path},
+    HttpRequest, HttpResponse,
+};
+use common::ctf_message::ChallengeId;
+use config::Config;
+use entity::entities::{challenge, challenge_flag, sea_orm_active_enums::FlagMatchMode, team};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, QueryFilter};
+use serde::Serialize;
+
+use crate::flag::team_flag;
+
+/// The per-team flags a team has to submit for a challenge
+#[derive(Serialize)]
+pub struct TeamFlags {
+    team_id: i32,
+    team_name: String,
+    flags: Vec<String>,
+}
+
+/// A challenge, so tooling can find the id of the one it's for
+#[derive(Serialize)]
+pub struct ApiChallenge {
+    id: ChallengeId,
+    title: String,
+    /// The challenge's folder in the repo
+    repo_path: Option<String>,
+}
+
+/// Every challenge that hasn't been retired
+#[get("/api/challenges")]
+pub async fn challenges_route(
+    req: HttpRequest,
+    db: Data<DatabaseConnection>,
+    config: Data<Config>,
+) -> HttpResponse {
+    if let Some(response) = unauthorized(&req, &config) {
+        return response;
+    }
+
+    let challenges = challenge::Entity::find()
+        .filter(challenge::Column::Retired.eq(false))
+        .all(db.get_ref())
+        .await
+        .expect("Failed to get challenges");
+
+    HttpResponse::Ok().json(
+        challenges
+            .into_iter()
+            .map(|challenge| ApiChallenge {
+                id: challenge.id,
+                title: challenge.title,
+                repo_path: challenge.repo_path,
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Every team's flags for a challenge
+#[get("/api/challenges/{challenge_id}/flags")]
+pub async fn challenge_flags_route(
+    req: HttpRequest,
+    challenge: Path<ChallengeId>,
+    db: Data<DatabaseConnection>,
+    config: Data<Config>,
+) -> HttpResponse {
+    let (secret, templates) = match load_templates(&req, *challenge, &db, &config).await {
+        Ok(loaded) => loaded,
+        Err(response) => return response,
+    };
+
+    let teams = team::Entity::find()
+        .all(db.get_ref())
+        .await
+        .expect("Failed to get teams");
+
+    HttpResponse::Ok().json(
+        teams
+            .iter()
+            .map(|team| team_flags(&secret, &templates, team))
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// One team's flags for a challenge
+#[get("/api/challenges/{challenge_id}/teams/{team_id}/flags")]
+pub async fn team_flags_route(
+    req: HttpRequest,
+    path: Path<(ChallengeId, i32)>,
+    db: Data<DatabaseConnection>,
+    config: Data<Config>,
+) -> HttpResponse {
+    let (challenge, team_id) = path.into_inner();
+
+    let (secret, templates) = match load_templates(&req, challenge, &db, &config).await {
+        Ok(loaded) => loaded,
+        Err(response) => return response,
+    };
+
+    match team::Entity::find_by_id(team_id)
+        .one(db.get_ref())
+        .await
+        .expect("Failed to get team")
+    {
+        Some(team) => HttpResponse::Ok().json(team_flags(&secret, &templates, &team)),
+        None => HttpResponse::NotFound().body("That team doesn't exist"),
+    }
+}
+
+/// The response to send back if the request isn't allowed
+fn unauthorized(req: &HttpRequest, config: &Config) -> Option<HttpResponse> {
+    // The API is turned off unless there's a token for it
+    let Some(api_token) = &config.flags.api_token else {
+        return Some(HttpResponse::NotFound().body("The API is turned off"));
+    };
+
+    let authorized = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| token == api_token);
+
+    if !authorized {
+        return Some(HttpResponse::Unauthorized().body("Missing or incorrect API token"));
+    }
+
+    None
+}
+
+/// Check the request is allowed, then find the challenge's per-team flag
+/// templates. Any problem is turned into the response to send back.
+async fn load_templates(
+    req: &HttpRequest,
+    challenge_id: ChallengeId,
+    db: &DatabaseConnection,
+    config: &Config,
+) -> Result<(String, Vec<challenge_flag::Model>), HttpResponse> {
+    if let Some(response) = unauthorized(req, config) {
+        return Err(response);
+    }
+
+    let Some(secret) = config.flags.secret.clone() else {
+        return Err(HttpResponse::NotFound().body("Per-team flags are turned off"));
+    };
+
+    let challenge = challenge::Entity::find_by_id(challenge_id)
+        .filter(challenge::Column::Retired.eq(false))
+        .one(db)
+        .await
+        .expect("Failed to get challenge")
+        .ok_or_else(|| HttpResponse::NotFound().body("That challenge doesn't exist"))?;
+
+    let templates = challenge
+        .find_related(challenge_flag::Entity)
+        .filter(challenge_flag::Column::MatchMode.eq(FlagMatchMode::PerTeam))
+        .all(db)
+        .await
+        .expect("Failed to get challenge flags");
+
+    if templates.is_empty() {
+        return Err(HttpResponse::NotFound().body("That challenge doesn't have per-team flags"));
+    }
+
+    Ok((secret, templates))
+}
+
+fn team_flags(secret: &str, templates: &[challenge_flag::Model], team: &team::Model) -> TeamFlags {
+    TeamFlags {
+        team_id: team.id,
+        team_name: team.name.clone(),
+        flags: templates
+            .iter()
+            .map(|template| team_flag(secret, &template.flag, team.id, template.fk_challenge_id))
+            .collect(),
+    }
+}
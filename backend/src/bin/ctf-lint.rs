@@ -0,0 +1,96 @@
+//! Check a challenge repo for problems without loading it into the database.
+//!
+//! Every problem is reported at once, so a challenge author can fix them all
+//! in one go. With `--json` the report is printed as JSON for CI. Exits with
+//! status 1 if anything is wrong.
+
+use std::path::PathBuf;
+
+use backend::repo::{flag_format_regex, Repo};
+use clap::Parser;
+use config::{Cli, Config};
+use serde_json::json;
+
+#[derive(Debug, Parser)]
+#[command(name = "ctf-lint", about = "Check a challenge repo for problems")]
+struct Args {
+    /// Challenge folder to check. Defaults to repo.path from the config.
+    path: Option<PathBuf>,
+
+    /// Path to the TOML config file
+    #[arg(long, short)]
+    config: Option<PathBuf>,
+
+    /// Regex every flag has to match. Defaults to repo.flag_format from the
+    /// config.
+    #[arg(long)]
+    flag_format: Option<String>,
+
+    /// Print the problems as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+fn main() {
+    let args = Args::parse();
+
+    // Load the config the same way the server does, with the environment
+    // variables it reads, but only the repo section has to be valid
+    let config = Cli::try_parse_from(["ctf-lint"])
+        .map_err(anyhow::Error::from)
+        .map(|cli| Cli {
+            config: args.config.or(cli.config),
+            ..cli
+        })
+        .and_then(Config::from_cli_unchecked)
+        .and_then(|config| config.repo.validate().map(|_| config));
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let path = args.path.unwrap_or(config.repo.path);
+    let flag_format = match args
+        .flag_format
+        .or(config.repo.flag_format)
+        .as_deref()
+        .map(flag_format_regex)
+        .transpose()
+    {
+        Ok(flag_format) => flag_format,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let (repo, problems) = Repo::check(&path, flag_format.as_ref());
+
+    if args.json {
+        println!(
+            "{}",
+            json!({
+                "path": path,
+                "challenges": repo.len(),
+                "problems": problems,
+            })
+        );
+    } else {
+        for problem in &problems {
+            println!("{}", problem);
+        }
+        println!(
+            "Checked {} challenge(s) in {}, found {} problem(s)",
+            repo.len(),
+            path.display(),
+            problems.len()
+        );
+    }
+
+    if !problems.is_empty() {
+        std::process::exit(1);
+    }
+}
@@ -1,28 +1,31 @@
 use actix::Addr;
 use chrono::NaiveDateTime;
-use common::ctf_message::CTFMessage;
+use common::{ctf_message::ClientMessage, unlocks::get_unlocked_challenges};
 use entity::{
-    entities::{hacker, submission, team},
-    helpers::get_team_unsolved_challenges,
+    entities::{challenge_flag, hacker, sea_orm_active_enums::FlagMatchMode, submission, team},
+    helpers::{get_team_unsolved_challenges, insert_hacker_with_free_username},
 };
 use rand::seq::SliceRandom;
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, QueryFilter, Set};
 
 use crate::messages::AnonymousCTFRequest;
 
 use super::{handlers::handle_request, ActixRecipient, ActixRequest, Auth, HandleData, RequestID, CTFServer};
 
 #[derive(Debug, Clone)]
-pub struct AITeams;
+pub struct AITeams {
+    // How many seconds it should take a team to solve a challenge on average
+    seconds_per_solve: f32,
+}
 
 impl AITeams {
-    pub fn new() -> Self {
-        AITeams
+    pub fn new(seconds_per_solve: f32) -> Self {
+        AITeams { seconds_per_solve }
     }
 
     // Each time this is run, roll for a chance of a team solving a challenge.
     // It will be rolled once a second, and each team should solve a challenge
-    // once every 1 minute on average.
+    // once every `seconds_per_solve` seconds on average.
     pub async fn run(&self, db: &DatabaseConnection, addr: &Addr<CTFServer>) {
         // Start by getting the list of teams
         let teams = team::Entity::find().all(db).await.unwrap();
@@ -32,7 +35,7 @@ impl AITeams {
             // Randomly roll for if they solve a challenge
 
             // Chance is how many seconds it should take on average to solve a challenge
-            let seconds_for_chance = 10.0;
+            let seconds_for_chance = self.seconds_per_solve;
 
             if rand::random::<f32>() < ((1.0) / seconds_for_chance) {
                 // Find a random player on this team to be the solver of the
@@ -48,15 +51,15 @@ impl AITeams {
                         match random_hacker {
                             Some(hacker) => hacker.clone(),
                             None => {
-                                // If there isn't a hacker, create one
+                                // If there isn't a hacker, create one. Every
+                                // AI team has one, so they're numbered.
                                 let hacker = hacker::ActiveModel {
                                     fk_team_id: Set(Some(team.id)),
-                                    // Choose a random discord id, 18 digits long
-                                    discord_id: Set(rand::random::<i64>()),
-                                    username: Set("AI Hacker".to_string()),
+                                    ..Default::default()
                                 };
-                                let hacker = hacker.insert(db).await.unwrap();
-                                hacker
+                                insert_hacker_with_free_username(db, "AI Hacker".to_string(), hacker)
+                                    .await
+                                    .unwrap()
                             }
                         }
                     }
@@ -67,9 +70,15 @@ impl AITeams {
                 };
 
                 // Find a challenge that this team hasn't solved. Do this by
-                // getting a list of all the challenges they have unsolved, and
-                // pick one of them at random.
-                let unsolved_challenges = get_team_unsolved_challenges(db, team.id).await;
+                // getting a list of all the challenges they have unsolved and
+                // unlocked, and pick one of them at random.
+                let unlocked = get_unlocked_challenges(Some(team.id), db).await;
+                let unsolved_challenges: Vec<_> = get_team_unsolved_challenges(db, team.id)
+                    .await
+                    .into_iter()
+                    .filter(|challenge| challenge.active && !challenge.retired)
+                    .filter(|challenge| unlocked.contains(&challenge.id))
+                    .collect();
 
                 // Pick a random challenge
                 let challenge = match unsolved_challenges.choose(&mut rand::thread_rng()) {
@@ -80,26 +89,42 @@ impl AITeams {
                     }
                 };
 
+                // Use a flag that can be submitted as is. Challenges that
+                // only have regex or per-team flags are skipped.
+                let flag = match challenge
+                    .find_related(challenge_flag::Entity)
+                    .filter(
+                        challenge_flag::Column::MatchMode
+                            .is_not_in([FlagMatchMode::Regex, FlagMatchMode::PerTeam]),
+                    )
+                    .one(db)
+                    .await
+                    .expect("Failed to get challenge flag")
+                {
+                    Some(flag) => flag.flag,
+                    None => continue,
+                };
+
                 // Send the message to the CTFServer actor
                 addr.do_send(AnonymousCTFRequest {
-                    ctf_message: CTFMessage::SubmitFlag {
-                        challenge_name: challenge.title.clone(),
-                        flag: challenge.flag.clone(),
+                    ctf_message: ClientMessage::SubmitFlag {
+                        challenge_id: challenge.id,
+                        flag,
                     },
-                    discord_id: hacker.discord_id,
+                    hacker_id: hacker.id,
                 });
 
                 // // Handle solving it
                 // handle_request(
                 //     Auth::Hacker {
-                //         discord_id: hacker.discord_id,
+                //         hacker_id: hacker.id,
                 //     },
                 //     HandleData {
                 //         db_clone: db.clone(),
                 //         tasks: &mut Vec::new(),
                 //         request: ActixRequest {
                 //             id: RequestID::Anonymous,
-                //             ctf_message: CTFMessage::SubmitFlag {
+                //             ctf_message: ClientMessage::SubmitFlag {
                 //                 challenge_name: challenge.title.clone(),
                 //                 flag: challenge.flag.clone(),
                 //             },
@@ -0,0 +1,29 @@
+use chrono::Utc;
+use entity::entities::audit_log;
+use log::{info, warn};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+
+use super::Auth;
+
+/// Keep a record of someone trying to do something that needs a role above a
+/// regular hacker, whether or not they were allowed to.
+pub async fn record(db: &DatabaseConnection, auth: &Auth, action: &str, allowed: bool) {
+    if allowed {
+        info!("{:?} ran {}", auth, action);
+    } else {
+        warn!("{:?} was denied {}", auth, action);
+    }
+
+    let entry = audit_log::ActiveModel {
+        time: Set(Utc::now().naive_utc()),
+        action: Set(action.to_string()),
+        allowed: Set(allowed),
+        fk_hacker_id: Set(auth.hacker_id()),
+        ..Default::default()
+    };
+
+    // Failing to write the log shouldn't stop the request from being handled
+    if let Err(e) = entry.insert(db).await {
+        warn!("Failed to write audit log entry: {}", e);
+    }
+}
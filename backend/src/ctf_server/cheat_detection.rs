@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+
+use chrono::{Duration, Utc};
+use config::Config;
+use entity::entities::{
+    challenge_flag, cheat_suspicion,
+    sea_orm_active_enums::{CheatReason, FlagMatchMode},
+    submission, team,
+};
+use log::warn;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+
+use crate::flag::team_flag;
+
+/// Look for signs that a wrong submission was copied from another team, and
+/// keep a record of any that turn up for the organizers to look at. A
+/// submission is suspicious if it's another team's per-team flag, or if
+/// another team recently got the challenge wrong with exactly the same thing.
+/// Short guesses don't count, since plenty of teams try `flag`.
+pub async fn check_submission(
+    db: &DatabaseConnection,
+    config: &Config,
+    submission: &submission::Model,
+    accepted_flags: &[challenge_flag::Model],
+) {
+    let (Some(team_id), Some(hacker_id), Some(challenge_id)) = (
+        submission.fk_team_id,
+        submission.fk_hacker_id,
+        submission.fk_challenge_id,
+    ) else {
+        return;
+    };
+
+    // Only report each other team once per submission
+    let mut source_teams = HashSet::new();
+
+    // See if it's any other team's per-team flag
+    if let Some(secret) = &config.flags.secret {
+        let templates = accepted_flags
+            .iter()
+            .filter(|flag| flag.match_mode == FlagMatchMode::PerTeam)
+            .collect::<Vec<_>>();
+
+        if !templates.is_empty() {
+            let other_teams = team::Entity::find()
+                .filter(team::Column::Id.ne(team_id))
+                .all(db)
+                .await
+                .expect("Failed to get teams");
+
+            for other_team in other_teams {
+                let matches = templates.iter().any(|template| {
+                    team_flag(secret, &template.flag, other_team.id, challenge_id)
+                        == submission.flag
+                });
+
+                if matches && source_teams.insert(other_team.id) {
+                    record(db, submission, CheatReason::TeamFlag, other_team.id, None).await;
+                }
+            }
+        }
+    }
+
+    // See if another team got it wrong with the same thing not long ago. A
+    // long wrong answer is unlikely to be guessed twice by chance.
+    let window_minutes = config.cheat_detection.shared_submission_window_minutes;
+    let long_enough =
+        submission.flag.chars().count() >= config.cheat_detection.shared_submission_min_length;
+    if window_minutes > 0 && long_enough {
+        let since = (Utc::now() - Duration::minutes(window_minutes as i64)).naive_utc();
+
+        let shared = submission::Entity::find()
+            .filter(submission::Column::FkChallengeId.eq(challenge_id))
+            .filter(submission::Column::FkTeamId.ne(team_id))
+            .filter(submission::Column::Flag.eq(submission.flag.clone()))
+            .filter(submission::Column::Correct.eq(false))
+            .filter(submission::Column::Time.gte(since))
+            .order_by_desc(submission::Column::Time)
+            .all(db)
+            .await
+            .expect("Failed to get recent submissions");
+
+        for other in shared {
+            let Some(other_team_id) = other.fk_team_id else {
+                continue;
+            };
+
+            if source_teams.insert(other_team_id) {
+                record(
+                    db,
+                    submission,
+                    CheatReason::SharedSubmission,
+                    other_team_id,
+                    other.fk_hacker_id,
+                )
+                .await;
+            }
+        }
+    }
+
+    if !source_teams.is_empty() {
+        warn!(
+            "Hacker {} submitted a flag for challenge {} that looks like it came from teams {:?}",
+            hacker_id, challenge_id, source_teams
+        );
+    }
+}
+
+async fn record(
+    db: &DatabaseConnection,
+    submission: &submission::Model,
+    reason: CheatReason,
+    source_team_id: i32,
+    source_hacker_id: Option<i64>,
+) {
+    let suspicion = cheat_suspicion::ActiveModel {
+        time: Set(submission.time),
+        reason: Set(reason),
+        flag: Set(submission.flag.clone()),
+        fk_challenge_id: Set(submission.fk_challenge_id.unwrap()),
+        fk_team_id: Set(submission.fk_team_id.unwrap()),
+        fk_hacker_id: Set(submission.fk_hacker_id.unwrap()),
+        fk_source_team_id: Set(source_team_id),
+        fk_source_hacker_id: Set(source_hacker_id),
+        ..Default::default()
+    };
+
+    // Failing to record it shouldn't stop the submission from being handled
+    if let Err(e) = suspicion.insert(db).await {
+        warn!("Failed to record cheat suspicion: {}", e);
+    }
+}
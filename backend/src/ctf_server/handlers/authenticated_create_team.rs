@@ -1,8 +1,10 @@
-use crate::ctf_server::{ActorTask, ActorTaskTo, CTFServer, HandleData, SendNetworkMessage};
+use crate::ctf_server::{
+    handlers::authenticated_leave_team::member_left, ActorTask, ActorTaskTo, CTFServer, HandleData,
+    SendNetworkMessage,
+};
 
-use common::{
-    ctf_message::{CTFClientStateComponent, CTFMessage, CTFState, ClientUpdate},
-    NetworkMessage,
+use common::ctf_message::{
+    CTFClientStateComponent, CTFState, ClientUpdate, GlobalDataUpdate, ServerMessage,
 };
 use entity::entities::{hacker, team};
 
@@ -10,12 +12,12 @@ use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
 
 use uuid::Uuid;
 
-pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, team_name: String, discord_id: i64) {
+pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, team_name: String, hacker_id: i64) {
     if team_name.is_empty() {
         CTFServer::send_message_associated(
-            NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(ClientUpdate::Notification(
+            ServerMessage::ClientUpdate(ClientUpdate::Notification(
                 "Team name cannot be empty".to_string(),
-            ))),
+            )),
             handle_data.recipient.clone(),
         );
 
@@ -30,8 +32,9 @@ pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, team_name: String,
         .is_some();
     if team_exists {
         CTFServer::send_message_associated(
-            NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(ClientUpdate::Notification(
-                format!("Team '{}' already exists", team_name),
+            ServerMessage::ClientUpdate(ClientUpdate::Notification(format!(
+                "Team '{}' already exists",
+                team_name
             ))),
             handle_data.recipient.clone(),
         );
@@ -39,40 +42,61 @@ pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, team_name: String,
         // Return tasks
         return;
     }
+    // Whoever makes the team is its captain
     let team = team::ActiveModel {
         name: Set(team_name),
         join_token: Set(Uuid::new_v4().as_simple().to_string()),
+        fk_captain_id: Set(Some(hacker_id)),
         ..Default::default()
     }
     .insert(&handle_data.db_clone)
     .await
     .unwrap();
-    let mut hacker: hacker::ActiveModel = hacker::Entity::find_by_id(discord_id)
+    let hacker = hacker::Entity::find_by_id(hacker_id)
         .one(&handle_data.db_clone)
         .await
         .expect("Failed to get hacker")
-        .unwrap()
-        .into();
+        .unwrap();
+    let old_team_id = hacker.fk_team_id;
+    let old_team = super::team_name(&handle_data.db_clone, old_team_id).await;
+    let mut hacker: hacker::ActiveModel = hacker.into();
     hacker.fk_team_id = Set(Some(team.id));
-    hacker.update(&handle_data.db_clone).await.unwrap();
+    let hacker = hacker.update(&handle_data.db_clone).await.unwrap();
+    if let Some(old_team_id) = old_team_id {
+        member_left(
+            &handle_data.db_clone,
+            old_team_id,
+            hacker_id,
+            handle_data.tasks,
+        )
+        .await;
+    }
+    handle_data
+        .tasks
+        .push(ActorTask::UpdateGlobalData(GlobalDataUpdate::TeamAdded {
+            name: team.name.clone(),
+        }));
+    handle_data
+        .tasks
+        .push(ActorTask::UpdateGlobalData(GlobalDataUpdate::HackerMoved {
+            hacker: hacker.username,
+            from: old_team,
+            to: Some(team.name.clone()),
+        }));
     handle_data
         .tasks
         .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
-            to: ActorTaskTo::Team(Vec::new()),
-            message: NetworkMessage::CTFMessage(CTFMessage::CTFClientStateComponent(
-                CTFClientStateComponent::GlobalData(
-                    CTFState::get_global_data(&handle_data.db_clone).await,
-                ),
+            to: ActorTaskTo::Session(handle_data.request.id),
+            message: ServerMessage::CTFClientStateComponent(CTFClientStateComponent::TeamData(
+                CTFState::get_hacker_team_data(hacker_id, &handle_data.db_clone).await,
             )),
         }));
     handle_data
         .tasks
         .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
             to: ActorTaskTo::Session(handle_data.request.id),
-            message: NetworkMessage::CTFMessage(CTFMessage::CTFClientStateComponent(
-                CTFClientStateComponent::TeamData(
-                    CTFState::get_hacker_team_data(discord_id, &handle_data.db_clone).await,
-                ),
+            message: ServerMessage::CTFClientStateComponent(CTFClientStateComponent::GameData(
+                CTFState::get_game_data(Some(team.id), &handle_data.db_clone).await,
             )),
         }));
     // TODO: Check if this user is already on a team
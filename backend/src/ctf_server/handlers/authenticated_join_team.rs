@@ -1,19 +1,20 @@
-use crate::ctf_server::{ActorTask, ActorTaskTo, CTFServer, HandleData, SendNetworkMessage};
+use crate::ctf_server::{
+    handlers::queue_team_data, ActorTask, ActorTaskTo, CTFServer, HandleData, SendNetworkMessage,
+};
 
-use common::{
-    ctf_message::{CTFClientStateComponent, CTFMessage, CTFState, ClientUpdate},
-    NetworkMessage,
+use common::ctf_message::{
+    CTFClientStateComponent, CTFState, ClientUpdate, GlobalDataUpdate, ServerMessage,
 };
 use entity::entities::{hacker, team};
 
 use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
 
-pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, token: String, discord_id: i64) {
+pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, token: String, hacker_id: i64) {
     if token.is_empty() {
         CTFServer::send_message_associated(
-            NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(ClientUpdate::Notification(
+            ServerMessage::ClientUpdate(ClientUpdate::Notification(
                 "Token cannot be empty".to_string(),
-            ))),
+            )),
             handle_data.recipient.clone(),
         );
 
@@ -33,9 +34,9 @@ pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, token: String, disc
         // If no team exists with this token, return an error message
         None => {
             CTFServer::send_message_associated(
-                NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(ClientUpdate::Notification(
+                ServerMessage::ClientUpdate(ClientUpdate::Notification(
                     "No team exists with this token".to_string(),
-                ))),
+                )),
                 handle_data.recipient.clone(),
             );
 
@@ -44,7 +45,7 @@ pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, token: String, disc
         Some(team) => {
             // Get the hacker associated with this request
             let hacker: hacker::Model = hacker::Entity::find()
-                .filter(hacker::Column::DiscordId.eq(discord_id))
+                .filter(hacker::Column::Id.eq(hacker_id))
                 .one(&handle_data.db_clone)
                 .await
                 .expect("Failed to get hacker")
@@ -53,8 +54,8 @@ pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, token: String, disc
             // If this hacker is already on a team, return an error message
             if hacker.fk_team_id.is_some() {
                 CTFServer::send_message_associated(
-                    NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(
-                        ClientUpdate::Notification("You are already on a team".to_string()),
+                    ServerMessage::ClientUpdate(ClientUpdate::Notification(
+                        "You are already on a team".to_string(),
                     )),
                     handle_data.recipient.clone(),
                 );
@@ -64,43 +65,68 @@ pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, token: String, disc
             }
 
             // Update the hacker's team id
+            let username = hacker.username.clone();
             let mut hacker: hacker::ActiveModel = hacker.into();
             hacker.fk_team_id = Set(Some(team.id));
-            let hacker_id = hacker.clone().discord_id.unwrap();
+            let hacker_id = hacker.clone().id.unwrap();
             hacker
                 .save(&handle_data.db_clone)
                 .await
                 .expect("Failed to update hacker");
 
+            // A team that lost its captain gets a new one with whoever joins
+            // next
+            if team.fk_captain_id.is_none() {
+                let mut team: team::ActiveModel = team.clone().into();
+                team.fk_captain_id = Set(Some(hacker_id));
+                team.update(&handle_data.db_clone)
+                    .await
+                    .expect("Failed to update team");
+            }
+
+            // Show everyone the hacker's new team
+            handle_data
+                .tasks
+                .push(ActorTask::UpdateGlobalData(GlobalDataUpdate::HackerMoved {
+                    hacker: username,
+                    from: None,
+                    to: Some(team.name.clone()),
+                }));
+
             // Send the hacker a message that they joined a team
             handle_data
                 .tasks
                 .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
                     to: ActorTaskTo::Session(handle_data.request.id),
-                    message: NetworkMessage::CTFMessage(CTFMessage::CTFClientStateComponent(
+                    message: ServerMessage::CTFClientStateComponent(
                         CTFClientStateComponent::ClientData(
                             CTFState::get_hacker_client_data(hacker_id, &handle_data.db_clone)
                                 .await,
                         ),
-                    )),
+                    ),
                 }));
 
-            // Send the hacker their team data
+            // Send the hacker their team data, and show the rest of the team
+            // their new member
+            queue_team_data(&handle_data.db_clone, team.id, handle_data.tasks).await;
+
+            // The hacker can now see what their team has unlocked
             handle_data
                 .tasks
                 .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
                     to: ActorTaskTo::Session(handle_data.request.id),
-                    message: NetworkMessage::CTFMessage(CTFMessage::CTFClientStateComponent(
-                        CTFClientStateComponent::TeamData(
-                            CTFState::get_hacker_team_data(hacker_id, &handle_data.db_clone).await,
+                    message: ServerMessage::CTFClientStateComponent(
+                        CTFClientStateComponent::GameData(
+                            CTFState::get_game_data(Some(team.id), &handle_data.db_clone).await,
                         ),
-                    )),
+                    ),
                 }));
 
             // Send the hacker a notification that they joined a team
             CTFServer::send_message_associated(
-                NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(ClientUpdate::Notification(
-                    format!("You joined team {}", team.name),
+                ServerMessage::ClientUpdate(ClientUpdate::Notification(format!(
+                    "You joined team {}",
+                    team.name
                 ))),
                 handle_data.recipient.clone(),
             );
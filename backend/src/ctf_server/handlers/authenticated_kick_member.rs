@@ -0,0 +1,99 @@
+use crate::ctf_server::{
+    handlers::{captain_team, notify_hacker, queue_team_data},
+    ActorTask, ActorTaskTo, CTFServer, HandleData, SendNetworkMessage,
+};
+
+use common::ctf_message::{
+    CTFClientStateComponent, CTFState, ClientUpdate, GlobalDataUpdate, ServerMessage, TeamData,
+};
+use entity::entities::hacker;
+
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+
+pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, username: String, hacker_id: i64) {
+    let (captain, team) = match captain_team(handle_data, hacker_id).await {
+        Some(captain_team) => captain_team,
+        None => return,
+    };
+
+    let member = hacker::Entity::find()
+        .filter(hacker::Column::FkTeamId.eq(team.id))
+        .filter(hacker::Column::Username.eq(&username))
+        .one(&handle_data.db_clone)
+        .await
+        .expect("Failed to get hacker");
+
+    let member = match member {
+        Some(member) if member.id == hacker_id => {
+            CTFServer::send_message_associated(
+                ServerMessage::ClientUpdate(ClientUpdate::Notification(
+                    "Make someone else captain or leave the team instead".to_string(),
+                )),
+                handle_data.recipient.clone(),
+            );
+            return;
+        }
+        Some(member) => member,
+        None => {
+            CTFServer::send_message_associated(
+                ServerMessage::ClientUpdate(ClientUpdate::Notification(format!(
+                    "{} isn't on your team",
+                    username
+                ))),
+                handle_data.recipient.clone(),
+            );
+            return;
+        }
+    };
+
+    let member_id = member.id;
+    let mut member: hacker::ActiveModel = member.into();
+    member.fk_team_id = Set(None);
+    member
+        .update(&handle_data.db_clone)
+        .await
+        .expect("Failed to update hacker");
+
+    // Tell every client the hacker isn't on the team anymore
+    handle_data
+        .tasks
+        .push(ActorTask::UpdateGlobalData(GlobalDataUpdate::HackerMoved {
+            hacker: username.clone(),
+            from: Some(team.name.clone()),
+            to: None,
+        }));
+
+    // The kicked hacker loses the team, and anything it unlocked
+    handle_data
+        .tasks
+        .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
+            to: ActorTaskTo::Hackers(vec![member_id]),
+            message: ServerMessage::CTFClientStateComponent(CTFClientStateComponent::TeamData(
+                TeamData::NoTeam,
+            )),
+        }));
+    handle_data
+        .tasks
+        .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
+            to: ActorTaskTo::Hackers(vec![member_id]),
+            message: ServerMessage::CTFClientStateComponent(CTFClientStateComponent::GameData(
+                CTFState::get_game_data(None, &handle_data.db_clone).await,
+            )),
+        }));
+    notify_hacker(
+        handle_data.tasks,
+        member_id,
+        format!("{} removed you from team {}", captain.username, team.name),
+    );
+
+    // Everyone left sees the new member list
+    queue_team_data(&handle_data.db_clone, team.id, handle_data.tasks).await;
+
+    CTFServer::send_message_associated(
+        ServerMessage::ClientUpdate(ClientUpdate::Notification(format!(
+            "Removed {} from the team",
+            username
+        ))),
+        handle_data.recipient.clone(),
+    );
+}
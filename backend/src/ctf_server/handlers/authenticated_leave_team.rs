@@ -1,50 +1,128 @@
-use crate::ctf_server::{ActorTask, ActorTaskTo, HandleData, SendNetworkMessage};
+use crate::ctf_server::{
+    handlers::{notify_hacker, queue_team_data, team_name},
+    ActorTask, ActorTaskTo, HandleData, SendNetworkMessage,
+};
 
-use common::{
-    ctf_message::{CTFClientStateComponent, CTFMessage, CTFState},
-    NetworkMessage,
+use common::ctf_message::{
+    CTFClientStateComponent, CTFState, GlobalDataUpdate, ServerMessage, TeamId,
 };
-use entity::entities::hacker;
+use entity::entities::{hacker, team};
 
-use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
 
-pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, discord_id: i64) {
+pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, hacker_id: i64) {
     // Extract the Discord ID from the agent
     // Check that this hacker is on a team
-    let mut hacker: hacker::ActiveModel = hacker::Entity::find_by_id(discord_id)
+    let hacker = match hacker::Entity::find_by_id(hacker_id)
         .one(&handle_data.db_clone)
         .await
         .expect("Failed to get hacker")
-        .unwrap()
-        .into();
+    {
+        Some(hacker) => hacker,
+        None => {
+            super::unexpected(handle_data, "Your account doesn't exist anymore");
+            return;
+        }
+    };
+    let old_team_id = hacker.fk_team_id;
+    let old_team = team_name(&handle_data.db_clone, old_team_id).await;
+    let username = hacker.username.clone();
+    let mut hacker: hacker::ActiveModel = hacker.into();
 
     // Set the hacker's team to empty
     hacker.fk_team_id = Set(None);
 
     // Save the hacker in the database
-    hacker.update(&handle_data.db_clone).await.unwrap();
+    hacker
+        .update(&handle_data.db_clone)
+        .await
+        .expect("Failed to update hacker");
 
-    // Broadcast this new GlobalData to every client
+    // Tell every client the hacker isn't on the team anymore
+    handle_data
+        .tasks
+        .push(ActorTask::UpdateGlobalData(GlobalDataUpdate::HackerMoved {
+            hacker: username,
+            from: old_team,
+            to: None,
+        }));
+
+    if let Some(old_team_id) = old_team_id {
+        member_left(
+            &handle_data.db_clone,
+            old_team_id,
+            hacker_id,
+            handle_data.tasks,
+        )
+        .await;
+    }
+
+    // Update the client's TeamData on their hacker leaving a team
     handle_data
         .tasks
         .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
-            to: ActorTaskTo::Team(Vec::new()),
-            message: NetworkMessage::CTFMessage(CTFMessage::CTFClientStateComponent(
-                CTFClientStateComponent::GlobalData(
-                    CTFState::get_global_data(&handle_data.db_clone).await,
-                ),
+            to: ActorTaskTo::Session(handle_data.request.id),
+            message: ServerMessage::CTFClientStateComponent(CTFClientStateComponent::TeamData(
+                CTFState::get_hacker_team_data(hacker_id, &handle_data.db_clone).await,
             )),
         }));
 
-    // Update the client's TeamData on their hacker leaving a team
+    // Without a team, they lose access to anything the team unlocked
     handle_data
         .tasks
         .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
             to: ActorTaskTo::Session(handle_data.request.id),
-            message: NetworkMessage::CTFMessage(CTFMessage::CTFClientStateComponent(
-                CTFClientStateComponent::TeamData(
-                    CTFState::get_hacker_team_data(discord_id, &handle_data.db_clone).await,
-                ),
+            message: ServerMessage::CTFClientStateComponent(CTFClientStateComponent::GameData(
+                CTFState::get_game_data(None, &handle_data.db_clone).await,
             )),
         }));
 }
+
+/// Tidy up a team after one of its members left it. If they were the captain,
+/// the member whose account is the oldest takes over, and everyone left sees
+/// the new member list.
+pub async fn member_left(
+    db: &DatabaseConnection,
+    team_id: TeamId,
+    hacker_id: i64,
+    tasks: &mut Vec<ActorTask>,
+) {
+    // Nothing to tidy up if the team is gone
+    let team = match team::Entity::find_by_id(team_id)
+        .one(db)
+        .await
+        .expect("Failed to get team")
+    {
+        Some(team) => team,
+        None => return,
+    };
+
+    if team.fk_captain_id == Some(hacker_id) {
+        let new_captain = hacker::Entity::find()
+            .filter(hacker::Column::FkTeamId.eq(team_id))
+            .order_by_asc(hacker::Column::Id)
+            .one(db)
+            .await
+            .expect("Failed to get hacker");
+
+        let team_name = team.name.clone();
+        let mut team: team::ActiveModel = team.into();
+        team.fk_captain_id = Set(new_captain.as_ref().map(|hacker| hacker.id));
+        team.update(db).await.expect("Failed to update team");
+
+        if let Some(new_captain) = new_captain {
+            notify_hacker(
+                tasks,
+                new_captain.id,
+                format!(
+                    "The captain left, so you're captain of team {} now",
+                    team_name
+                ),
+            );
+        }
+    }
+
+    queue_team_data(db, team_id, tasks).await;
+}
@@ -0,0 +1,38 @@
+use crate::ctf_server::{ActorTask, HandleData, UpdateState};
+
+use common::ctf_message::HackerId;
+use entity::entities::token;
+
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>) {
+    // If a client wants to log out, deauthenticate their stream
+    handle_data
+        .tasks
+        .push(ActorTask::UpdateState(UpdateState::Logout));
+
+    // The token they logged in with shouldn't work anymore, including in any
+    // other windows that are using it
+    if let Some(session_token) = handle_data.token.clone() {
+        token::Entity::delete_by_id(session_token.clone())
+            .exec(&handle_data.db_clone)
+            .await
+            .expect("Failed to delete token");
+
+        handle_data
+            .tasks
+            .push(ActorTask::RevokeToken(session_token));
+    }
+}
+
+/// Throw away every token this hacker has, and log out all of their sessions,
+/// including the one this came from if it's theirs
+pub async fn revoke_hacker(handle_data: &mut HandleData<'_>, hacker_id: HackerId) {
+    token::Entity::delete_many()
+        .filter(token::Column::FkHackerId.eq(hacker_id))
+        .exec(&handle_data.db_clone)
+        .await
+        .expect("Failed to delete tokens");
+
+    handle_data.tasks.push(ActorTask::RevokeHacker(hacker_id));
+}
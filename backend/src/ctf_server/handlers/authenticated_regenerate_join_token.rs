@@ -0,0 +1,35 @@
+use crate::ctf_server::{
+    handlers::{captain_team, queue_team_data},
+    CTFServer, HandleData,
+};
+
+use common::ctf_message::{ClientUpdate, ServerMessage};
+use entity::entities::team;
+
+use sea_orm::{ActiveModelTrait, Set};
+
+use uuid::Uuid;
+
+pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, hacker_id: i64) {
+    let (_, team) = match captain_team(handle_data, hacker_id).await {
+        Some(captain_team) => captain_team,
+        None => return,
+    };
+
+    let team_id = team.id;
+    let mut team: team::ActiveModel = team.into();
+    team.join_token = Set(Uuid::new_v4().as_simple().to_string());
+    team.update(&handle_data.db_clone)
+        .await
+        .expect("Failed to update team");
+
+    // Every member gets the new token to share
+    queue_team_data(&handle_data.db_clone, team_id, handle_data.tasks).await;
+
+    CTFServer::send_message_associated(
+        ServerMessage::ClientUpdate(ClientUpdate::Notification(
+            "Made a new join token, the old one doesn't work anymore".to_string(),
+        )),
+        handle_data.recipient.clone(),
+    );
+}
@@ -0,0 +1,23 @@
+use crate::ctf_server::{reload::reload_challenges, CTFServer, HandleData};
+
+use common::ctf_message::{ClientUpdate, ServerMessage};
+
+/// Load the challenges from the repo again, and tell the organizer what
+/// changed
+pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>) {
+    let notification = match reload_challenges(
+        &handle_data.config.repo,
+        &handle_data.db_clone,
+        handle_data.tasks,
+    )
+    .await
+    {
+        Ok(changes) => format!("Reloaded the challenges: {}", changes),
+        Err(e) => format!("Failed to reload the challenges: {:#}", e),
+    };
+
+    CTFServer::send_message_associated(
+        ServerMessage::ClientUpdate(ClientUpdate::Notification(notification)),
+        handle_data.recipient.clone(),
+    );
+}
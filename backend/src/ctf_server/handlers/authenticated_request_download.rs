@@ -0,0 +1,30 @@
+use crate::{
+    ctf_server::{CTFServer, HandleData},
+    files::find_file,
+};
+
+use common::ctf_message::{ChallengeId, ClientUpdate, ServerMessage};
+
+pub async fn handle<'a>(
+    handle_data: &'a mut HandleData<'a>,
+    challenge_id: ChallengeId,
+    name: String,
+    hacker_id: i64,
+) {
+    // Turn them away now rather than with a link that doesn't work
+    let message = match find_file(&handle_data.db_clone, hacker_id, challenge_id, &name).await {
+        Ok(_) => ClientUpdate::DownloadReady {
+            token: handle_data
+                .downloads
+                .start(hacker_id, challenge_id, name.clone()),
+            challenge_id,
+            name,
+        },
+        Err(e) => ClientUpdate::Notification(e.to_string()),
+    };
+
+    CTFServer::send_message_associated(
+        ServerMessage::ClientUpdate(message),
+        handle_data.recipient.clone(),
+    );
+}
@@ -0,0 +1,38 @@
+use crate::ctf_server::{handlers::authenticated_logout::revoke_hacker, CTFServer, HandleData};
+
+use common::ctf_message::{ClientUpdate, ServerMessage};
+use entity::entities::hacker;
+
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, username: String) {
+    // Organizers only know hackers by name, so log out everyone with it
+    let hackers = hacker::Entity::find()
+        .filter(hacker::Column::Username.eq(&username))
+        .all(&handle_data.db_clone)
+        .await
+        .expect("Failed to get hackers");
+
+    if hackers.is_empty() {
+        CTFServer::send_message_associated(
+            ServerMessage::ClientUpdate(ClientUpdate::Notification(format!(
+                "No hacker is called {}",
+                username
+            ))),
+            handle_data.recipient.clone(),
+        );
+        return;
+    }
+
+    for hacker in hackers {
+        revoke_hacker(handle_data, hacker.id).await;
+    }
+
+    CTFServer::send_message_associated(
+        ServerMessage::ClientUpdate(ClientUpdate::Notification(format!(
+            "Logged out {} everywhere",
+            username
+        ))),
+        handle_data.recipient.clone(),
+    );
+}
@@ -1,37 +1,78 @@
-use crate::ctf_server::{ActorTask, ActorTaskTo, CTFServer, HandleData, SendNetworkMessage};
+use crate::{
+    ctf_server::{
+        cheat_detection, handlers::queue_team_game_data, ActorTask, ActorTaskTo, CTFServer,
+        HandleData, SendNetworkMessage,
+    },
+    flag::any_matches,
+};
 
 use chrono::NaiveDateTime;
 use common::{
-    ctf_message::{CTFClientStateComponent, CTFMessage, CTFState, ClientUpdate},
-    NetworkMessage,
+    ctf_message::{ChallengeId, ClientUpdate, GlobalDataUpdate, ServerMessage, Solve, TeamId},
+    scoring::challenge_points,
+    unlocks::get_unlocked_challenges,
+};
+use entity::{
+    entities::{challenge, challenge_flag, hacker, submission, team},
+    helpers::{get_challenge_solve_counts, get_event},
 };
-use entity::entities::{challenge, hacker, submission, team};
 
 use log::info;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, QueryFilter,
+    QuerySelect, Set, TransactionTrait,
+};
 
 pub async fn handle<'a>(
     handle_data: &'a mut HandleData<'a>,
-    challenge_name: String,
-    discord_id: i64,
+    challenge_id: ChallengeId,
+    hacker_id: i64,
     flag: String,
 ) {
-    let challenge = challenge::Entity::find()
-        .filter(challenge::Column::Title.eq(&challenge_name))
-        .one(&handle_data.db_clone)
-        .await
-        .expect("Failed to get challenge");
+    // Slow down anyone submitting too quickly before anything is looked up,
+    // so a flood of submissions doesn't turn into a flood of queries
+    let limited = handle_data
+        .rate_limiter
+        .lock()
+        .unwrap()
+        .check_hacker(hacker_id);
+    if let Err(retry_after) = limited {
+        rate_limited(handle_data, retry_after);
+        return;
+    }
+
+    // Flags can only be submitted while the CTF is running
+    let event = get_event(&handle_data.db_clone).await;
+    if let Some(event) = &event {
+        let now = chrono::Utc::now().naive_utc();
 
-    let hacker = hacker::Entity::find_by_id(discord_id)
+        let closed_reason = if now < event.start {
+            Some("The CTF hasn't started yet")
+        } else if now > event.end {
+            Some("The CTF is over, flags can't be submitted anymore")
+        } else {
+            None
+        };
+
+        if let Some(closed_reason) = closed_reason {
+            CTFServer::send_message_associated(
+                ServerMessage::ClientUpdate(ClientUpdate::Notification(closed_reason.to_string())),
+                handle_data.recipient.clone(),
+            );
+            return;
+        }
+    }
+
+    let hacker = hacker::Entity::find_by_id(hacker_id)
         .one(&handle_data.db_clone)
         .await
         .expect("Failed to get hacker");
 
     if hacker.as_ref().unwrap().fk_team_id.is_none() {
         CTFServer::send_message_associated(
-            NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(ClientUpdate::Notification(
+            ServerMessage::ClientUpdate(ClientUpdate::Notification(
                 "You are not on a team, you can't submit a flag".to_string(),
-            ))),
+            )),
             handle_data.recipient.clone(),
         );
         return;
@@ -43,9 +84,30 @@ pub async fn handle<'a>(
         .expect("Failed to get team")
         .expect("Didn't find the team in the database");
 
+    // Teams and challenges have their own limits, so a whole team can't get
+    // around it by taking turns
+    let limited = handle_data
+        .rate_limiter
+        .lock()
+        .unwrap()
+        .check_team(team.id, challenge_id);
+    if let Err(retry_after) = limited {
+        rate_limited(handle_data, retry_after);
+        return;
+    }
+
+    // Retired challenges are treated as if they don't exist
+    let challenge = challenge::Entity::find_by_id(challenge_id)
+        .filter(challenge::Column::Retired.eq(false))
+        .one(&handle_data.db_clone)
+        .await
+        .expect("Failed to get challenge");
+
     // Next, we'll check if this team has already solved this challenge
     let existing_correct_submission = submission::Entity::find()
-        .filter(submission::Column::FkChallengeId.eq(challenge.as_ref().unwrap().id))
+        .filter(
+            submission::Column::FkChallengeId.eq(challenge.as_ref().map(|challenge| challenge.id)),
+        )
         .filter(submission::Column::FkTeamId.eq(team.id))
         .filter(submission::Column::Correct.eq(true))
         .one(&handle_data.db_clone)
@@ -54,14 +116,29 @@ pub async fn handle<'a>(
 
     if existing_correct_submission.is_some() {
         CTFServer::send_message_associated(
-            NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(ClientUpdate::Notification(
+            ServerMessage::ClientUpdate(ClientUpdate::Notification(
                 "Your team has already solved this challenge!".to_string(),
-            ))),
+            )),
             handle_data.recipient.clone(),
         );
         return;
     }
 
+    // Challenges can't be solved until the team has unlocked them
+    let unlocked = get_unlocked_challenges(Some(team.id), &handle_data.db_clone).await;
+    if let Some(challenge) = &challenge {
+        if !unlocked.contains(&challenge.id) {
+            CTFServer::send_message_associated(
+                ServerMessage::ClientUpdate(ClientUpdate::Notification(format!(
+                    "Your team hasn't unlocked {} yet",
+                    challenge.title
+                ))),
+                handle_data.recipient.clone(),
+            );
+            return;
+        }
+    }
+
     let now: std::time::Duration = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap();
@@ -72,8 +149,10 @@ pub async fn handle<'a>(
         time: Set(
             NaiveDateTime::from_timestamp_opt(now.as_secs() as i64, now.subsec_nanos()).unwrap(),
         ),
-        fk_hacker_id: Set(Some(hacker.unwrap().discord_id)),
+        fk_hacker_id: Set(Some(hacker.unwrap().id)),
         fk_team_id: Set(Some(team.id)),
+        blood: Set(None),
+        bonus_points: Set(0),
         ..Default::default()
     };
 
@@ -81,57 +160,184 @@ pub async fn handle<'a>(
         Some(challenge) => {
             submission.fk_challenge_id = Set(Some(challenge.id));
 
-            // See if this channel's flag matches the flag they submitted
-            if challenge.flag == flag ||
-            // TODO: Remove this lol
-            flag == "flag"
-            {
+            let accepted_flags = challenge
+                .find_related(challenge_flag::Entity)
+                .all(&handle_data.db_clone)
+                .await
+                .expect("Failed to get challenge flags");
+
+            // See if any of this challenge's flags match the flag they
+            // submitted
+            let submission = if any_matches(
+                &accepted_flags,
+                &flag,
+                team.id,
+                handle_data.config.flags.secret.as_deref(),
+            ) {
+                submission.correct = Set(true);
+
+                let (saved, solves) = match record_solve(
+                    &handle_data.db_clone,
+                    &handle_data.config.first_blood.bonus_points,
+                    challenge.id,
+                    team.id,
+                    submission,
+                )
+                .await
+                {
+                    Some(solve) => solve,
+                    // A teammate got there first
+                    None => {
+                        CTFServer::send_message_associated(
+                            ServerMessage::ClientUpdate(ClientUpdate::Notification(
+                                "Your team has already solved this challenge!".to_string(),
+                            )),
+                            handle_data.recipient.clone(),
+                        );
+                        return;
+                    }
+                };
+
+                let points = challenge_points(&challenge, solves) + saved.bonus_points;
+
                 let recipient_clone = handle_data.recipient.clone();
                 CTFServer::send_message_associated(
-                    NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(
-                        ClientUpdate::ScoredPoint(format!(
-                            "You solved {} for {} points!",
-                            challenge.title, challenge.points
-                        )),
+                    ServerMessage::ClientUpdate(ClientUpdate::ScoredPoint(
+                        match blood_name(solves) {
+                            Some(blood) => format!(
+                                "{}! You solved {} for {} points!",
+                                blood, challenge.title, points
+                            ),
+                            None => {
+                                format!("You solved {} for {} points!", challenge.title, points)
+                            }
+                        },
                     )),
                     recipient_clone,
                 );
 
-                // Change the submission
-                submission.correct = Set(true);
+                saved
             } else {
                 let recipient_clone = handle_data.recipient.clone();
                 CTFServer::send_message_associated(
-                    NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(
-                        ClientUpdate::ScoredPoint(format!(
-                            "That flag didn't solve {}",
-                            challenge_name
-                        )),
-                    )),
+                    ServerMessage::ClientUpdate(ClientUpdate::ScoredPoint(format!(
+                        "That flag didn't solve {}",
+                        challenge.title
+                    ))),
                     recipient_clone,
                 );
 
-                // Change the submission
+                // Save the submission to the database
                 submission.correct = Set(false);
-            }
+                submission.insert(&handle_data.db_clone).await.unwrap()
+            };
 
-            let solved = *submission.correct.as_ref();
+            let solved = submission.correct;
+            let blood = submission.blood.map(|blood| blood as u8);
 
-            // Save the submission to the database
-            submission.insert(&handle_data.db_clone).await.unwrap();
+            // A wrong flag might be one that was shared by another team
+            if !solved {
+                cheat_detection::check_submission(
+                    &handle_data.db_clone,
+                    &handle_data.config,
+                    &submission,
+                    &accepted_flags,
+                )
+                .await;
+            }
 
             if solved {
-                // Notify all the online clients about a scoreboard update
-                handle_data
-                    .tasks
-                    .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
-                        to: ActorTaskTo::BroadcastAll,
-                        message: NetworkMessage::CTFMessage(CTFMessage::CTFClientStateComponent(
-                            CTFClientStateComponent::GlobalData(
-                                CTFState::get_global_data(&handle_data.db_clone).await,
-                            ),
-                        )),
-                    }));
+                let now_unlocked =
+                    get_unlocked_challenges(Some(team.id), &handle_data.db_clone).await;
+
+                // Every solve of this challenge is worth a bit less now, as far
+                // as the public scoreboard knows
+                let freeze = event.as_ref().and_then(|event| event.freeze);
+                let value = challenge_points(
+                    &challenge,
+                    get_challenge_solve_counts(&handle_data.db_clone, freeze)
+                        .await
+                        .get(&challenge.id)
+                        .copied()
+                        .unwrap_or(0),
+                );
+
+                // Notify all the online clients about a scoreboard update.
+                // Solves after the scoreboard freeze are kept off of it, and
+                // so is the new value they'd give the challenge.
+                let frozen = matches!(freeze, Some(freeze) if submission.time >= freeze);
+                if !frozen {
+                    handle_data.tasks.push(ActorTask::UpdateGlobalData(
+                        GlobalDataUpdate::SolveAdded {
+                            team: team.name.clone(),
+                            solve: Solve {
+                                points: value + submission.bonus_points,
+                                time_millis: submission.time.timestamp_millis() as u128,
+                                blood: submission.blood.map(|blood| blood as u8),
+                                challenge_id: Some(challenge.id),
+                                bonus_points: submission.bonus_points,
+                            },
+                        },
+                    ));
+                    handle_data.tasks.push(ActorTask::UpdateGlobalData(
+                        GlobalDataUpdate::ChallengeRescored {
+                            challenge_id: challenge.id,
+                            points: value,
+                        },
+                    ));
+                }
+
+                // The team's challenge list shows the solve, and anything it
+                // unlocked
+                queue_team_game_data(&handle_data.db_clone, team.id, handle_data.tasks).await;
+
+                // Tell the team about anything this solve unlocked
+                let newly_unlocked: Vec<String> = challenge::Entity::find()
+                    .all(&handle_data.db_clone)
+                    .await
+                    .expect("Failed to get challenges")
+                    .into_iter()
+                    .filter(|challenge| challenge.active && !challenge.retired)
+                    .filter(|challenge| !unlocked.contains(&challenge.id))
+                    .filter(|challenge| now_unlocked.contains(&challenge.id))
+                    .map(|challenge| challenge.title)
+                    .collect();
+
+                if !newly_unlocked.is_empty() {
+                    let members = hacker::Entity::find()
+                        .filter(hacker::Column::FkTeamId.eq(team.id))
+                        .all(&handle_data.db_clone)
+                        .await
+                        .expect("Failed to get team members")
+                        .into_iter()
+                        .map(|hacker| hacker.id)
+                        .collect();
+
+                    handle_data
+                        .tasks
+                        .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
+                            to: ActorTaskTo::Hackers(members),
+                            message: ServerMessage::ClientUpdate(ClientUpdate::ChallengesUnlocked(
+                                newly_unlocked,
+                            )),
+                        }));
+                }
+
+                // Let everyone know about first bloods, unless the solve came
+                // after the scoreboard froze
+                if let (Some(blood), false) = (blood, frozen) {
+                    handle_data
+                        .tasks
+                        .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
+                            to: ActorTaskTo::BroadcastAll,
+                            message: ServerMessage::ClientUpdate(ClientUpdate::FirstBlood {
+                                team: team.name.clone(),
+                                challenge: challenge.title.clone(),
+                                challenge_id: challenge.id,
+                                blood,
+                            }),
+                        }));
+                }
 
                 println!("{} solved {}", team.name, challenge.title);
             }
@@ -140,11 +346,85 @@ pub async fn handle<'a>(
             // Tell them that this challenge doesn't exist
             let recipient_clone = handle_data.recipient.clone();
             CTFServer::send_message_associated(
-                NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(ClientUpdate::ScoredPoint(
+                ServerMessage::ClientUpdate(ClientUpdate::ScoredPoint(
                     "That challenge does not exist".to_string(),
-                ))),
+                )),
                 recipient_clone,
             )
         }
     }
 }
+
+/// What to call the nth solve of a challenge, if it's one of the first three
+fn blood_name(solves: u64) -> Option<&'static str> {
+    match solves {
+        1 => Some("First blood"),
+        2 => Some("Second blood"),
+        3 => Some("Third blood"),
+        _ => None,
+    }
+}
+
+/// Save a correct submission, and work out which solve of the challenge it is.
+/// The challenge is locked until it's saved, so two teams solving it at the
+/// same moment can't both draw the same blood. Returns the submission and how
+/// many teams have solved the challenge now, or None if the team already had.
+async fn record_solve(
+    db: &DatabaseConnection,
+    blood_bonus_points: &[i32],
+    challenge_id: ChallengeId,
+    team_id: TeamId,
+    mut submission: submission::ActiveModel,
+) -> Option<(submission::Model, u64)> {
+    let txn = db.begin().await.expect("Failed to start transaction");
+
+    challenge::Entity::find_by_id(challenge_id)
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .expect("Failed to lock challenge");
+
+    let solves = submission::Entity::find()
+        .filter(submission::Column::FkChallengeId.eq(challenge_id))
+        .filter(submission::Column::Correct.eq(true))
+        .all(&txn)
+        .await
+        .expect("Failed to get solves");
+
+    // Dropping the transaction rolls it back
+    if solves.iter().any(|solve| solve.fk_team_id == Some(team_id)) {
+        return None;
+    }
+
+    // Count this solve as well, since it isn't saved yet
+    let place = solves.len() as u64 + 1;
+
+    // The first three teams to solve a challenge draw blood, and might get a
+    // bonus for it
+    if place <= 3 {
+        submission.blood = Set(Some(place as i32));
+        submission.bonus_points = Set(blood_bonus_points
+            .get(place as usize - 1)
+            .copied()
+            .unwrap_or(0));
+    }
+
+    let submission = submission
+        .insert(&txn)
+        .await
+        .expect("Failed to save submission");
+    txn.commit().await.expect("Failed to save submission");
+
+    Some((submission, place))
+}
+
+/// Tell the hacker how long they have to wait before they can submit again
+fn rate_limited(handle_data: &HandleData, retry_after: std::time::Duration) {
+    CTFServer::send_message_associated(
+        ServerMessage::ClientUpdate(ClientUpdate::RateLimited {
+            // Round up so they don't try again a moment too early
+            retry_after: retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0),
+        }),
+        handle_data.recipient.clone(),
+    );
+}
@@ -0,0 +1,65 @@
+use crate::ctf_server::{
+    handlers::{captain_team, notify_hacker, queue_team_data},
+    CTFServer, HandleData,
+};
+
+use common::ctf_message::{ClientUpdate, ServerMessage};
+use entity::entities::{hacker, team};
+
+use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+
+pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, username: String, hacker_id: i64) {
+    let (captain, team) = match captain_team(handle_data, hacker_id).await {
+        Some(captain_team) => captain_team,
+        None => return,
+    };
+
+    let member = hacker::Entity::find()
+        .filter(hacker::Column::FkTeamId.eq(team.id))
+        .filter(hacker::Column::Username.eq(&username))
+        .one(&handle_data.db_clone)
+        .await
+        .expect("Failed to get hacker");
+
+    let member = match member.filter(|member| member.id != hacker_id) {
+        Some(member) => member,
+        None => {
+            CTFServer::send_message_associated(
+                ServerMessage::ClientUpdate(ClientUpdate::Notification(format!(
+                    "{} isn't anyone else on your team",
+                    username
+                ))),
+                handle_data.recipient.clone(),
+            );
+            return;
+        }
+    };
+
+    let team_id = team.id;
+    let team_name = team.name.clone();
+    let mut team: team::ActiveModel = team.into();
+    team.fk_captain_id = Set(Some(member.id));
+    team.update(&handle_data.db_clone)
+        .await
+        .expect("Failed to update team");
+
+    notify_hacker(
+        handle_data.tasks,
+        member.id,
+        format!(
+            "{} made you captain of team {}",
+            captain.username, team_name
+        ),
+    );
+
+    // The captain's controls move over to the new captain
+    queue_team_data(&handle_data.db_clone, team_id, handle_data.tasks).await;
+
+    CTFServer::send_message_associated(
+        ServerMessage::ClientUpdate(ClientUpdate::Notification(format!(
+            "{} is the captain now",
+            username
+        ))),
+        handle_data.recipient.clone(),
+    );
+}
@@ -0,0 +1,186 @@
+use crate::ctf_server::{
+    handlers::team_name, ActorTask, ActorTaskTo, CTFServer, HandleData, SendNetworkMessage,
+};
+
+use common::{
+    ctf_message::{
+        CTFClientStateComponent, CTFState, ChallengeId, ClientUpdate, GlobalDataUpdate,
+        ServerMessage, Solve,
+    },
+    unlocks::get_unlocked_challenges,
+};
+use entity::{
+    entities::{challenge, hacker, hint, hint_unlock},
+    helpers::get_event,
+};
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder, Set,
+};
+
+pub async fn handle<'a>(
+    handle_data: &'a mut HandleData<'a>,
+    challenge_id: ChallengeId,
+    hint_position: usize,
+    hacker_id: i64,
+) {
+    // Hints cost points, so they can only be bought while the CTF is running
+    let event = get_event(&handle_data.db_clone).await;
+    if let Some(event) = &event {
+        let now = chrono::Utc::now().naive_utc();
+        if now < event.start || now > event.end {
+            notify(
+                handle_data,
+                "Hints can only be unlocked while the CTF is running",
+            );
+            return;
+        }
+    }
+
+    let hacker = hacker::Entity::find_by_id(hacker_id)
+        .one(&handle_data.db_clone)
+        .await
+        .expect("Failed to get hacker")
+        .expect("Didn't find the hacker in the database");
+
+    let team_id = match hacker.fk_team_id {
+        Some(team_id) => team_id,
+        None => {
+            notify(handle_data, "You are not on a team, you can't unlock hints");
+            return;
+        }
+    };
+
+    let challenge = match challenge::Entity::find_by_id(challenge_id)
+        .filter(challenge::Column::Retired.eq(false))
+        .one(&handle_data.db_clone)
+        .await
+        .expect("Failed to get challenge")
+    {
+        Some(challenge) => challenge,
+        None => {
+            notify(handle_data, "That challenge does not exist");
+            return;
+        }
+    };
+
+    if !get_unlocked_challenges(Some(team_id), &handle_data.db_clone)
+        .await
+        .contains(&challenge.id)
+    {
+        notify(
+            handle_data,
+            &format!("Your team hasn't unlocked {} yet", challenge.title),
+        );
+        return;
+    }
+
+    let hints = challenge
+        .find_related(hint::Entity)
+        .order_by_asc(hint::Column::Position)
+        .all(&handle_data.db_clone)
+        .await
+        .expect("Failed to get hints");
+
+    let hint = match hints.get(hint_position) {
+        Some(hint) => hint,
+        None => {
+            notify(handle_data, "That hint does not exist");
+            return;
+        }
+    };
+
+    let unlocked_hints: Vec<i32> = hint_unlock::Entity::find()
+        .filter(hint_unlock::Column::FkTeamId.eq(team_id))
+        .all(&handle_data.db_clone)
+        .await
+        .expect("Failed to get hint unlocks")
+        .into_iter()
+        .map(|unlock| unlock.fk_hint_id)
+        .collect();
+
+    if unlocked_hints.contains(&hint.id) {
+        notify(handle_data, "Your team has already unlocked this hint");
+        return;
+    }
+
+    // Hints are unlocked in order, so the cheap nudges come before the
+    // expensive giveaways
+    if hints[..hint_position]
+        .iter()
+        .any(|earlier| !unlocked_hints.contains(&earlier.id))
+    {
+        notify(handle_data, "Unlock the hints before this one first");
+        return;
+    }
+
+    let unlock = hint_unlock::ActiveModel {
+        time: Set(chrono::Utc::now().naive_utc()),
+        fk_hint_id: Set(hint.id),
+        fk_team_id: Set(team_id),
+        fk_hacker_id: Set(hacker_id),
+        ..Default::default()
+    }
+    .insert(&handle_data.db_clone)
+    .await
+    .expect("Failed to unlock hint");
+
+    // The hint comes off the team's score, unless the scoreboard is frozen
+    let frozen = matches!(
+        event.as_ref().and_then(|event| event.freeze),
+        Some(freeze) if unlock.time >= freeze
+    );
+    if let Some(team) = team_name(&handle_data.db_clone, Some(team_id))
+        .await
+        .filter(|_| !frozen)
+    {
+        handle_data
+            .tasks
+            .push(ActorTask::UpdateGlobalData(GlobalDataUpdate::SolveAdded {
+                team,
+                solve: Solve {
+                    points: -hint.cost,
+                    time_millis: unlock.time.timestamp_millis() as u128,
+                    blood: None,
+                    challenge_id: None,
+                    bonus_points: 0,
+                },
+            }));
+    }
+
+    // Show the hint to everyone on the team
+    let members: Vec<i64> = hacker::Entity::find()
+        .filter(hacker::Column::FkTeamId.eq(team_id))
+        .all(&handle_data.db_clone)
+        .await
+        .expect("Failed to get team members")
+        .into_iter()
+        .map(|hacker| hacker.id)
+        .collect();
+
+    handle_data
+        .tasks
+        .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
+            to: ActorTaskTo::Hackers(members.clone()),
+            message: ServerMessage::CTFClientStateComponent(CTFClientStateComponent::GameData(
+                CTFState::get_game_data(Some(team_id), &handle_data.db_clone).await,
+            )),
+        }));
+
+    handle_data
+        .tasks
+        .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
+            to: ActorTaskTo::Hackers(members),
+            message: ServerMessage::ClientUpdate(ClientUpdate::Notification(format!(
+                "{} unlocked a hint for {} for {} points",
+                hacker.username, challenge.title, hint.cost
+            ))),
+        }));
+}
+
+fn notify(handle_data: &HandleData<'_>, message: &str) {
+    CTFServer::send_message_associated(
+        ServerMessage::ClientUpdate(ClientUpdate::Notification(message.to_string())),
+        handle_data.recipient.clone(),
+    );
+}
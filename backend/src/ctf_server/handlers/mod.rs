@@ -1,64 +1,144 @@
-use crate::repo::Repo;
-use actix::prelude::*;
-use common::ctf_message::CTFMessage;
+use std::collections::HashMap;
 
-use entity::entities::team;
+use crate::{accounts, event};
+use common::ctf_message::{
+    CTFClientStateComponent, CTFState, ClientMessage, ClientUpdate, ServerError, ServerMessage,
+    TeamId, TeamName,
+};
+
+use entity::entities::{hacker, sea_orm_active_enums::Role, team};
 use migration::{Migrator, MigratorTrait};
-use sea_orm::{EntityTrait, Set};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
 
-use super::{ActorTask, Auth, HandleData, UpdateState};
+use super::{
+    audit, reload::refetch_repo, ActorTask, ActorTaskTo, Auth, CTFServer, HandleData,
+    SendNetworkMessage,
+};
 
 pub mod authenticated_create_team;
 pub mod authenticated_join_team;
+pub mod authenticated_kick_member;
 pub mod authenticated_leave_team;
+pub mod authenticated_logout;
+pub mod authenticated_regenerate_join_token;
+pub mod authenticated_reload_challenges;
+pub mod authenticated_request_download;
+pub mod authenticated_revoke_hacker;
 pub mod authenticated_submit_flag;
+pub mod authenticated_transfer_captain;
+pub mod authenticated_unlock_hint;
 pub mod unauthenticated_connect;
 pub mod unauthenticated_login;
+pub mod unauthenticated_password_login;
+pub mod unauthenticated_register;
 
 pub async fn handle_request(auth: Auth, mut handle_data: HandleData<'_>) {
     let db_clone = handle_data.db_clone.clone();
+    let repo_config = handle_data.config.repo.clone();
+    let event_config = handle_data.config.event.clone();
+    let accounts_config = handle_data.config.accounts.clone();
+    let recipient = handle_data.recipient.clone();
 
     let ctf_message_clone_1 = handle_data.request.ctf_message.clone();
     let ctf_message_clone_2 = handle_data.request.ctf_message.clone();
 
-    match auth {
+    // Some messages are organizer or admin actions, so make sure this session
+    // is allowed to run them before doing anything
+    if let Some(role) = required_role(&ctf_message_clone_2) {
+        let action = format!("{:?}", ctf_message_clone_2);
+        let allowed = auth.has_role(role);
+
+        audit::record(&db_clone, &auth, &action, allowed).await;
+
+        if !allowed {
+            CTFServer::send_message_associated(
+                ServerMessage::ClientUpdate(ClientUpdate::PermissionDenied(format!(
+                    "You need to be {:?} to run {}",
+                    role, action
+                ))),
+                recipient,
+            );
+
+            return;
+        }
+    }
+
+    match auth.clone() {
         // If they are unauthenticated, the only message we'll take from
         // them is a login message.and TODO: Should this also allow
         // public data to be seen? TODO: What happens if you try to log
         // in after you
         Auth::Unauthenticated => match ctf_message_clone_1 {
-            CTFMessage::Login(token) => {
+            ClientMessage::Login(token) => {
                 unauthenticated_login::handle(&mut handle_data, token).await;
             }
-            CTFMessage::Connect => {
+            ClientMessage::Register {
+                username,
+                password,
+                invite_code,
+            } => {
+                unauthenticated_register::handle(&mut handle_data, username, password, invite_code)
+                    .await;
+            }
+            ClientMessage::PasswordLogin { username, password } => {
+                unauthenticated_password_login::handle(&mut handle_data, username, password).await;
+            }
+            ClientMessage::Connect => {
                 unauthenticated_connect::handle(&mut handle_data).await;
             }
-            _ => (),
+            ClientMessage::RequestGlobalData => {
+                handle_data
+                    .tasks
+                    .push(ActorTask::SendGlobalData(ActorTaskTo::Session(
+                        handle_data.request.id,
+                    )));
+            }
+            ClientMessage::Resume(session_id) => {
+                handle_data.tasks.push(ActorTask::ResumeSession(session_id));
+            }
+            // The handshake is finished before messages get this far
+            ClientMessage::Hello { .. } => (),
+            _ => unexpected(&mut handle_data, "You need to log in first"),
         },
-        Auth::Hacker { discord_id } => {
+        Auth::Hacker { hacker_id } | Auth::Organizer { hacker_id } | Auth::Admin { hacker_id } => {
             match ctf_message_clone_1 {
-                CTFMessage::CTFClientStateComponent(_) => todo!(),
-                CTFMessage::SubmitFlag {
-                    challenge_name,
-                    flag,
-                } => {
+                ClientMessage::Hello { .. } => (),
+                ClientMessage::SubmitFlag { challenge_id, flag } => {
                     authenticated_submit_flag::handle(
                         &mut handle_data,
-                        challenge_name,
-                        discord_id,
+                        challenge_id,
+                        hacker_id,
                         flag,
                     )
                     .await
                 }
-                CTFMessage::ClientUpdate(_) => todo!(),
+                ClientMessage::UnlockHint { challenge_id, hint } => {
+                    authenticated_unlock_hint::handle(
+                        &mut handle_data,
+                        challenge_id,
+                        hint,
+                        hacker_id,
+                    )
+                    .await
+                }
+                ClientMessage::RequestDownload { challenge_id, name } => {
+                    authenticated_request_download::handle(
+                        &mut handle_data,
+                        challenge_id,
+                        name,
+                        hacker_id,
+                    )
+                    .await
+                }
                 // TODO: This can be hit after logout for some reason
-                CTFMessage::Login(_) => todo!(),
-                CTFMessage::Logout => {
-                    // If a client wants to log out, deauthenticate
-                    // their stream
-                    handle_data
-                        .tasks
-                        .push(ActorTask::UpdateState(UpdateState::Logout));
+                ClientMessage::Login(_)
+                | ClientMessage::Register { .. }
+                | ClientMessage::PasswordLogin { .. }
+                | ClientMessage::Resume(_) => {
+                    unexpected(&mut handle_data, "You're already logged in")
+                }
+                ClientMessage::Logout => {
+                    authenticated_logout::handle(&mut handle_data).await;
 
                     // TODO: update everyone that this player has gone
                     // offline
@@ -66,74 +146,277 @@ pub async fn handle_request(auth: Auth, mut handle_data: HandleData<'_>) {
                     // return vec![ActorTask::SendNetworkMessage(
                     //     SendNetworkMessage { to:
                     //         ActorTaskTo::Session(msg.id), message:
-                    //         NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(
-                    //             ClientUpdate::Logout, )), }, )]
+                    //         ServerMessage::ClientUpdate(
+                    //             ClientUpdate::Logout, ), }, )]
+                }
+                ClientMessage::LogoutEverywhere => {
+                    authenticated_logout::revoke_hacker(&mut handle_data, hacker_id).await;
+                }
+                ClientMessage::JoinTeam(token) => {
+                    authenticated_join_team::handle(&mut handle_data, token, hacker_id).await
+                }
+                ClientMessage::CreateTeam(team_name) => {
+                    authenticated_create_team::handle(&mut handle_data, team_name, hacker_id).await
+                }
+                ClientMessage::LeaveTeam => {
+                    authenticated_leave_team::handle(&mut handle_data, hacker_id).await;
                 }
-                CTFMessage::JoinTeam(token) => {
-                    authenticated_join_team::handle(&mut handle_data, token, discord_id).await
+                ClientMessage::KickMember(username) => {
+                    authenticated_kick_member::handle(&mut handle_data, username, hacker_id).await;
                 }
-                CTFMessage::CreateTeam(team_name) => {
-                    authenticated_create_team::handle(&mut handle_data, team_name, discord_id).await
+                ClientMessage::TransferCaptain(username) => {
+                    authenticated_transfer_captain::handle(&mut handle_data, username, hacker_id)
+                        .await;
                 }
-                CTFMessage::LeaveTeam => {
-                    authenticated_leave_team::handle(&mut handle_data, discord_id).await;
+                ClientMessage::RegenerateJoinToken => {
+                    authenticated_regenerate_join_token::handle(&mut handle_data, hacker_id).await;
+                }
+                // A logged in client reconnecting just needs the current
+                // state again
+                ClientMessage::Connect => {
+                    handle_data
+                        .tasks
+                        .push(ActorTask::SendGlobalData(ActorTaskTo::Session(
+                            handle_data.request.id,
+                        )));
+                }
+                ClientMessage::CloneRepo | ClientMessage::ReloadChallenges => {
+                    authenticated_reload_challenges::handle(&mut handle_data).await
+                }
+                ClientMessage::RequestGlobalData => {
+                    handle_data
+                        .tasks
+                        .push(ActorTask::SendGlobalData(ActorTaskTo::Session(
+                            handle_data.request.id,
+                        )));
+                }
+                // Organizer and admin actions. The session's role was checked
+                // before anything else was done.
+                ClientMessage::ResetDB => {
+                    println!("Resetting database");
+                    // Download the repo and load the challenges from it,
+                    // before anything is thrown away
+                    let repo = match refetch_repo(&repo_config).await {
+                        Ok(repo) => repo,
+                        Err(e) => {
+                            CTFServer::send_message_associated(
+                                ServerMessage::ClientUpdate(ClientUpdate::Notification(format!(
+                                    "Failed to load the challenges: {:#}",
+                                    e
+                                ))),
+                                recipient,
+                            );
+                            return;
+                        }
+                    };
+
+                    // Rerun the migrations on the database
+                    Migrator::fresh(&db_clone).await.unwrap();
+
+                    // Load all the challenges found into the database
+                    if let Err(e) = repo.update_database(&db_clone).await {
+                        CTFServer::send_message_associated(
+                            ServerMessage::ClientUpdate(ClientUpdate::Notification(format!(
+                                "Failed to save the challenges: {}",
+                                e
+                            ))),
+                            recipient,
+                        );
+                        return;
+                    }
+
+                    // Put the event schedule back
+                    event::update_database(&event_config, &db_clone).await;
+
+                    // And the invite codes
+                    accounts::update_database(&accounts_config, &db_clone).await;
+
+                    println!("Spawn 10 teams");
+                    // Spawn 1000 teams
+                    spawn_teams(&db_clone).await;
+
+                    // Everything anyone could see is different now
+                    handle_data.tasks.push(ActorTask::ReplaceGlobalData(
+                        CTFState::get_global_data(&db_clone).await,
+                    ));
+                }
+                ClientMessage::SpawnTeams => {
+                    println!("Spawning teams");
+                    // Spawn 1000 teams
+                    spawn_teams(&db_clone).await;
+
+                    handle_data.tasks.push(ActorTask::ReplaceGlobalData(
+                        CTFState::get_global_data(&db_clone).await,
+                    ));
+                }
+                ClientMessage::RequestCheatSuspicions => {
+                    CTFServer::send_message_associated(
+                        ServerMessage::ClientUpdate(ClientUpdate::CheatSuspicions(
+                            CTFState::get_cheat_suspicions(&db_clone).await,
+                        )),
+                        recipient,
+                    );
+                }
+                ClientMessage::RevokeHacker(username) => {
+                    authenticated_revoke_hacker::handle(&mut handle_data, username).await;
                 }
-                CTFMessage::Connect => todo!(),
-                CTFMessage::ResetDB => (),
-                CTFMessage::SpawnTeams => (),
-                CTFMessage::CloneRepo => (),
             }
         }
     }
+}
+
+async fn spawn_teams(db: &DatabaseConnection) {
+    team::Entity::insert_many((0..10).map(|i| team::ActiveModel {
+        name: Set(format!("Team {}", i)),
+        join_token: Set("".to_string()),
+        ..Default::default()
+    }))
+    .exec(db)
+    .await
+    .unwrap();
+}
 
-    match ctf_message_clone_2 {
-        CTFMessage::ResetDB => {
-            println!("Resetting database");
-            // Rerun the migrations on the database
-            Migrator::fresh(&db_clone).await.unwrap();
-
-            // Download the repo
-            Repo::clone_repo();
-
-            // Load the repo from the repository
-            let repo = Repo::parse_repo();
-
-            // Load all the challenges found into the database
-            repo.update_database().await;
-
-            println!("Spawn 10 teams");
-            // Spawn 1000 teams
-            team::Entity::insert_many((0..10).map(|i| team::ActiveModel {
-                name: Set(format!("Team {}", i)),
-                join_token: Set("".to_string()),
-                ..Default::default()
-            }))
-            .exec(&db_clone)
-            .await
-            .unwrap();
+/// Get the name of a team, for updates to the GlobalData
+pub async fn team_name(db: &DatabaseConnection, team_id: Option<TeamId>) -> Option<TeamName> {
+    team::Entity::find_by_id(team_id?)
+        .one(db)
+        .await
+        .expect("Failed to get team")
+        .map(|team| team.name)
+}
+
+/// The captain and team of the hacker that sent this request. If they aren't
+/// a team captain, they're told so and this is None.
+async fn captain_team(
+    handle_data: &mut HandleData<'_>,
+    hacker_id: i64,
+) -> Option<(hacker::Model, team::Model)> {
+    let (captain, team) = match hacker::Entity::find_by_id(hacker_id)
+        .find_also_related(team::Entity)
+        .one(&handle_data.db_clone)
+        .await
+        .expect("Failed to get hacker")
+    {
+        Some(found) => found,
+        None => {
+            unexpected(handle_data, "Your account doesn't exist anymore");
+            return None;
         }
-        CTFMessage::SpawnTeams => {
-            println!("Spawning teams");
-            // Spawn 1000 teams
-            team::Entity::insert_many((0..10).map(|i| team::ActiveModel {
-                name: Set(format!("Team {}", i)),
-                join_token: Set("".to_string()),
-                ..Default::default()
-            }))
-            .exec(&db_clone)
-            .await
-            .unwrap();
+    };
+
+    match team {
+        Some(team) if team.fk_captain_id == Some(hacker_id) => Some((captain, team)),
+        _ => {
+            CTFServer::send_message_associated(
+                ServerMessage::ClientUpdate(ClientUpdate::PermissionDenied(
+                    "Only the team captain can do that".to_string(),
+                )),
+                handle_data.recipient.clone(),
+            );
+            None
         }
-        CTFMessage::CloneRepo => {
-            // Download the repo
-            Repo::clone_repo();
+    }
+}
 
-            // Load the repo from the repository
-            let repo = Repo::parse_repo();
+/// Send everyone on a team their TeamData again, after the members, the
+/// captain or the join token changed
+pub async fn queue_team_data(db: &DatabaseConnection, team_id: TeamId, tasks: &mut Vec<ActorTask>) {
+    let hackers = hacker::Entity::find()
+        .filter(hacker::Column::FkTeamId.eq(team_id))
+        .all(db)
+        .await
+        .expect("Failed to get hackers");
 
-            // Load all the challenges found into the database
-            repo.update_database().await;
-        }
-        _ => (),
+    // Only the captain's TeamData says they're captain, so everyone gets
+    // their own
+    for hacker in hackers {
+        tasks.push(ActorTask::SendNetworkMessage(SendNetworkMessage {
+            to: ActorTaskTo::Hackers(vec![hacker.id]),
+            message: ServerMessage::CTFClientStateComponent(CTFClientStateComponent::TeamData(
+                CTFState::get_hacker_team_data(hacker.id, db).await,
+            )),
+        }));
+    }
+}
+
+/// Let a hacker know something happened to them, on every session they have
+fn notify_hacker(tasks: &mut Vec<ActorTask>, hacker_id: i64, notification: String) {
+    tasks.push(ActorTask::SendNetworkMessage(SendNetworkMessage {
+        to: ActorTaskTo::Hackers(vec![hacker_id]),
+        message: ServerMessage::ClientUpdate(ClientUpdate::Notification(notification)),
+    }));
+}
+
+/// Send every hacker the challenge list as their team sees it, after the
+/// challenges themselves changed
+pub async fn queue_game_data(db: &DatabaseConnection, tasks: &mut Vec<ActorTask>) {
+    let teams: HashMap<Option<i32>, Vec<i64>> = hacker::Entity::find()
+        .all(db)
+        .await
+        .expect("Failed to get hackers")
+        .into_iter()
+        .fold(HashMap::new(), |mut teams, hacker| {
+            teams
+                .entry(hacker.fk_team_id)
+                .or_insert_with(Vec::new)
+                .push(hacker.id);
+            teams
+        });
+
+    for (team_id, hackers) in teams {
+        tasks.push(ActorTask::SendNetworkMessage(SendNetworkMessage {
+            to: ActorTaskTo::Hackers(hackers),
+            message: ServerMessage::CTFClientStateComponent(CTFClientStateComponent::GameData(
+                CTFState::get_game_data(team_id, db).await,
+            )),
+        }));
+    }
+}
+
+/// Send one team the challenge list again. When a team solves a challenge,
+/// only that team's unlocks can change. Everyone else keeps their challenge
+/// list up to date from the ChallengeRescored and FirstBlood that go with it.
+pub async fn queue_team_game_data(
+    db: &DatabaseConnection,
+    team_id: TeamId,
+    tasks: &mut Vec<ActorTask>,
+) {
+    let members = hacker::Entity::find()
+        .filter(hacker::Column::FkTeamId.eq(team_id))
+        .all(db)
+        .await
+        .expect("Failed to get team members")
+        .into_iter()
+        .map(|hacker| hacker.id)
+        .collect();
+
+    tasks.push(ActorTask::SendNetworkMessage(SendNetworkMessage {
+        to: ActorTaskTo::Hackers(members),
+        message: ServerMessage::CTFClientStateComponent(CTFClientStateComponent::GameData(
+            CTFState::get_game_data(Some(team_id), db).await,
+        )),
+    }));
+}
+
+/// Tell the session that sent this request that it wasn't expected right now
+fn unexpected(handle_data: &mut HandleData, reason: &str) {
+    handle_data
+        .tasks
+        .push(ActorTask::SendNetworkMessage(SendNetworkMessage {
+            to: ActorTaskTo::Session(handle_data.request.id),
+            message: ServerMessage::Error(ServerError::UnexpectedMessage(reason.to_string())),
+        }));
+}
+
+/// The role a session needs to send this message, if it needs more than just
+/// being logged in
+fn required_role(ctf_message: &ClientMessage) -> Option<Role> {
+    match ctf_message {
+        ClientMessage::ResetDB | ClientMessage::SpawnTeams => Some(Role::Admin),
+        ClientMessage::CloneRepo
+        | ClientMessage::ReloadChallenges
+        | ClientMessage::RequestCheatSuspicions
+        | ClientMessage::RevokeHacker(_) => Some(Role::Organizer),
+        _ => None,
     }
 }
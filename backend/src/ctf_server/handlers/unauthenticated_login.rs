@@ -1,18 +1,27 @@
 use crate::{
     ctf_server::{
-        ActixRequest, ActorTask, ActorTaskTo, Auth, CTFServer, HandleData, SendNetworkMessage,
-        UpdateState,
+        handlers::team_name, ActixRequest, ActorTask, ActorTaskTo, Auth, CTFServer, HandleData,
+        SendNetworkMessage, UpdateState,
     },
     messages::IncomingCTFRequest,
 };
 
-use common::{
-    ctf_message::{CTFClientStateComponent, CTFMessage, CTFState, ClientUpdate},
-    NetworkMessage,
+use chrono::{Duration, Utc};
+use common::ctf_message::{
+    CTFClientStateComponent, CTFState, ClientUpdate, GlobalDataUpdate, Hacker, HackerId,
+    ServerMessage,
+};
+use config::{RolesConfig, SessionsConfig};
+use entity::entities::{
+    hacker,
+    sea_orm_active_enums::{Role, TokenKind},
+    token,
 };
-use entity::entities::{hacker, token};
 
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, QueryFilter, Set,
+};
+use uuid::Uuid;
 
 pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, token: String) {
     // Find any tokens in the database that match this token
@@ -23,33 +32,61 @@ pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, token: String) {
         .await
         .expect("Failed to get token");
 
+    // A token that's too old can't be used now, or ever again
+    let token = match token {
+        Some(token) if token.expiry <= Utc::now().naive_utc() => {
+            token
+                .delete(&handle_data.db_clone)
+                .await
+                .expect("Failed to delete expired token");
+
+            CTFServer::send_message_associated(
+                ServerMessage::ClientUpdate(ClientUpdate::TokenExpired),
+                handle_data.recipient.clone(),
+            );
+            return;
+        }
+        token => token,
+    };
+
     // If we have that token, then we can authenticate this websocket connection
     // as the user they say they are
     match token {
         Some(token) => {
             // Get the hacker associated with this token
-            let hacker = hacker::Entity::find_by_id(token.fk_hacker_id.unwrap())
-                .one(&handle_data.db_clone)
-                .await
-                .expect("Failed to get hacker");
+            let hacker = match token.fk_hacker_id {
+                Some(hacker_id) => hacker::Entity::find_by_id(hacker_id)
+                    .one(&handle_data.db_clone)
+                    .await
+                    .expect("Failed to get hacker"),
+                None => None,
+            };
 
             // If we have a hacker, then we can authenticate this websocket
             // connection as the user they say they are
             match hacker {
                 Some(hacker) => {
-                    update_authenticated_user(
-                        handle_data.tasks,
-                        &handle_data.request,
-                        hacker,
+                    let token = session_token(
                         token,
+                        hacker.id,
+                        &handle_data.config.sessions,
                         &handle_data.db_clone,
                     )
                     .await;
+
+                    authenticate(handle_data, hacker, token).await;
                 }
-                // If this token doesn't have a hacker associated with it,
-                // something is wrong. This is unreachable.
+                // A token that doesn't belong to anyone is no use to anyone
                 None => {
-                    panic!("Token has no hacker associated with it");
+                    token
+                        .delete(&handle_data.db_clone)
+                        .await
+                        .expect("Failed to delete token without a hacker");
+
+                    CTFServer::send_message_associated(
+                        ServerMessage::ClientUpdate(ClientUpdate::IncorrectToken),
+                        handle_data.recipient.clone(),
+                    );
                 }
             }
         }
@@ -57,13 +94,112 @@ pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, token: String) {
             // If we don't have that token, then we can't authenticate this
             // websocket connection
             CTFServer::send_message_associated(
-                NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(ClientUpdate::IncorrectToken)),
+                ServerMessage::ClientUpdate(ClientUpdate::IncorrectToken),
                 handle_data.recipient.clone(),
             );
         }
     }
 }
 
+/// Authenticate this session as a hacker who has proven who they are, with
+/// the token their browser should keep
+pub async fn authenticate(
+    handle_data: &mut HandleData<'_>,
+    hacker: hacker::Model,
+    token: token::Model,
+) {
+    let hacker =
+        apply_configured_role(hacker, &handle_data.config.roles, &handle_data.db_clone).await;
+
+    update_authenticated_user(
+        handle_data.tasks,
+        &handle_data.request,
+        hacker,
+        token,
+        &handle_data.db_clone,
+    )
+    .await;
+}
+
+/// If the config lists this hacker as an admin or organizer, make sure their
+/// role in the database matches before they're authenticated. Accounts with a
+/// password keep the role their invite code gave them.
+async fn apply_configured_role(
+    hacker: hacker::Model,
+    roles: &RolesConfig,
+    db_clone: &DatabaseConnection,
+) -> hacker::Model {
+    let configured_role = match hacker.discord_id {
+        Some(discord_id) if roles.admins.contains(&discord_id) => Role::Admin,
+        Some(discord_id) if roles.organizers.contains(&discord_id) => Role::Organizer,
+        _ => return hacker,
+    };
+
+    if hacker.role == configured_role {
+        return hacker;
+    }
+
+    let mut hacker: hacker::ActiveModel = hacker.into();
+    hacker.role = Set(configured_role);
+    hacker
+        .update(db_clone)
+        .await
+        .expect("Failed to update hacker role")
+}
+
+/// The token the hacker's browser should keep. A login token from the Discord
+/// bot is swapped for a new session token, and a session token that's more
+/// than halfway through its life is given a new expiry.
+async fn session_token(
+    token: token::Model,
+    hacker_id: HackerId,
+    sessions: &SessionsConfig,
+    db_clone: &DatabaseConnection,
+) -> token::Model {
+    let lifetime = Duration::days(sessions.token_lifetime_days);
+    let now = Utc::now().naive_utc();
+
+    match token.kind {
+        TokenKind::Login => {
+            let session_token = new_session_token(hacker_id, sessions, db_clone).await;
+
+            // Login tokens only work once
+            token
+                .delete(db_clone)
+                .await
+                .expect("Failed to delete login token");
+
+            session_token
+        }
+        TokenKind::Session if token.expiry - now < lifetime / 2 => {
+            let mut token: token::ActiveModel = token.into();
+            token.expiry = Set(now + lifetime);
+            token
+                .update(db_clone)
+                .await
+                .expect("Failed to refresh token")
+        }
+        TokenKind::Session => token,
+    }
+}
+
+/// Give a hacker a new token for their browser to keep
+pub async fn new_session_token(
+    hacker_id: HackerId,
+    sessions: &SessionsConfig,
+    db_clone: &DatabaseConnection,
+) -> token::Model {
+    token::ActiveModel {
+        token: Set(Uuid::new_v4().as_simple().to_string()),
+        expiry: Set(Utc::now().naive_utc() + Duration::days(sessions.token_lifetime_days)),
+        fk_hacker_id: Set(Some(hacker_id)),
+        kind: Set(TokenKind::Session),
+    }
+    .insert(db_clone)
+    .await
+    .expect("Failed to create session token")
+}
+
 async fn update_authenticated_user(
     tasks: &mut Vec<ActorTask>,
     request: &ActixRequest,
@@ -74,65 +210,51 @@ async fn update_authenticated_user(
     // Tell the client they are authenticated
     tasks.push(ActorTask::SendNetworkMessage(SendNetworkMessage {
         to: ActorTaskTo::Session(request.id),
-        message: NetworkMessage::CTFMessage(CTFMessage::ClientUpdate(
-            ClientUpdate::Authenticated {
-                discord_username: hacker.username.clone(),
-                valid_token: token.token.clone(),
-            },
-        )),
+        message: ServerMessage::ClientUpdate(ClientUpdate::Authenticated {
+            username: hacker.username.clone(),
+            valid_token: token.token.clone(),
+        }),
     }));
 
-    // Get the updated state from the database.
-    let global_data = CTFState::get_global_data(db_clone).await;
-
-    // Tell every other player that this player has logged in
-    tasks.push(ActorTask::SendNetworkMessage(SendNetworkMessage {
-        to: ActorTaskTo::BroadcastAll,
-        message: NetworkMessage::CTFMessage(CTFMessage::CTFClientStateComponent(
-            CTFClientStateComponent::GlobalData(global_data.clone()),
-        )),
+    // Tell every other player about this player, if this is the first they've
+    // seen of them
+    tasks.push(ActorTask::UpdateGlobalData(GlobalDataUpdate::HackerAdded {
+        hacker: Hacker {
+            name: hacker.username.clone(),
+        },
+        team: team_name(db_clone, hacker.fk_team_id).await,
     }));
 
     // Send this client the current game state
     tasks.push(ActorTask::SendNetworkMessage(SendNetworkMessage {
         to: ActorTaskTo::Session(request.id),
-        message: NetworkMessage::CTFMessage(CTFMessage::CTFClientStateComponent(
-            CTFClientStateComponent::GameData(CTFState::get_game_data(db_clone).await),
+        message: ServerMessage::CTFClientStateComponent(CTFClientStateComponent::GameData(
+            CTFState::get_game_data(hacker.fk_team_id, db_clone).await,
         )),
     }));
 
     // Update this session's auth state
     tasks.push(ActorTask::UpdateState(UpdateState::SessionAuth {
-        auth: Auth::Hacker {
-            discord_id: hacker.discord_id,
-        },
+        auth: Auth::from_hacker(&hacker),
+        token: token.token.clone(),
     }));
 
     // Update the team on their hacker coming online
     tasks.push(ActorTask::SendNetworkMessage(SendNetworkMessage {
         to: ActorTaskTo::Session(request.id),
-        message: NetworkMessage::CTFMessage(CTFMessage::CTFClientStateComponent(
-            CTFClientStateComponent::TeamData(
-                CTFState::get_hacker_team_data(hacker.discord_id, db_clone).await,
-            ),
+        message: ServerMessage::CTFClientStateComponent(CTFClientStateComponent::TeamData(
+            CTFState::get_hacker_team_data(hacker.id, db_clone).await,
         )),
     }));
 
     // Update the client on their hacker coming online
     tasks.push(ActorTask::SendNetworkMessage(SendNetworkMessage {
         to: ActorTaskTo::Session(request.id),
-        message: NetworkMessage::CTFMessage(CTFMessage::CTFClientStateComponent(
-            CTFClientStateComponent::ClientData(
-                CTFState::get_hacker_client_data(hacker.discord_id, db_clone).await,
-            ),
+        message: ServerMessage::CTFClientStateComponent(CTFClientStateComponent::ClientData(
+            CTFState::get_hacker_client_data(hacker.id, db_clone).await,
         )),
     }));
 
     // Update the client with the current scoreboard
-    tasks.push(ActorTask::SendNetworkMessage(SendNetworkMessage {
-        to: ActorTaskTo::Session(request.id),
-        message: NetworkMessage::CTFMessage(CTFMessage::CTFClientStateComponent(
-            CTFClientStateComponent::GlobalData(CTFState::get_global_data(db_clone).await),
-        )),
-    }));
+    tasks.push(ActorTask::SendGlobalData(ActorTaskTo::Session(request.id)));
 }
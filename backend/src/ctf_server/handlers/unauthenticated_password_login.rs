@@ -0,0 +1,62 @@
+use crate::{
+    accounts,
+    ctf_server::{
+        handlers::unauthenticated_login::{authenticate, new_session_token},
+        CTFServer, HandleData,
+    },
+};
+
+use common::ctf_message::{ClientUpdate, ServerMessage};
+use entity::entities::hacker;
+
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+pub async fn handle<'a>(handle_data: &'a mut HandleData<'a>, username: String, password: String) {
+    if !handle_data.config.accounts.enabled {
+        super::unexpected(handle_data, "Accounts with a password are turned off");
+        return;
+    }
+
+    // Hackers from Discord have no password, so they can't be logged in as
+    // here
+    let hacker = hacker::Entity::find()
+        .filter(hacker::Column::Username.eq(username.trim()))
+        .filter(hacker::Column::PasswordHash.is_not_null())
+        .one(&handle_data.db_clone)
+        .await
+        .expect("Failed to get hacker");
+
+    let hacker = match hacker {
+        Some(hacker) => {
+            let password_hash = hacker.password_hash.clone().unwrap();
+
+            if accounts::verify_password(password, password_hash).await {
+                hacker
+            } else {
+                incorrect_password(handle_data);
+                return;
+            }
+        }
+        None => {
+            incorrect_password(handle_data);
+            return;
+        }
+    };
+
+    let token = new_session_token(
+        hacker.id,
+        &handle_data.config.sessions,
+        &handle_data.db_clone,
+    )
+    .await;
+
+    authenticate(handle_data, hacker, token).await;
+}
+
+/// Don't say whether it was the username or password that was wrong
+fn incorrect_password(handle_data: &HandleData) {
+    CTFServer::send_message_associated(
+        ServerMessage::ClientUpdate(ClientUpdate::IncorrectPassword),
+        handle_data.recipient.clone(),
+    );
+}
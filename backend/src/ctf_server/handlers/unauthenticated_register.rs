@@ -0,0 +1,128 @@
+use crate::{
+    accounts,
+    ctf_server::{
+        handlers::unauthenticated_login::{authenticate, new_session_token},
+        CTFServer, HandleData,
+    },
+};
+
+use common::ctf_message::{ClientUpdate, Role, ServerMessage};
+use entity::{
+    entities::{hacker, invite_code},
+    helpers::insert_hacker,
+};
+
+use sea_orm::{sea_query::Expr, ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
+
+/// Longest username anyone can register with
+const MAX_USERNAME_LENGTH: usize = 32;
+
+pub async fn handle<'a>(
+    handle_data: &'a mut HandleData<'a>,
+    username: String,
+    password: String,
+    invite_code: String,
+) {
+    if !handle_data.config.accounts.enabled {
+        super::unexpected(handle_data, "Accounts with a password are turned off");
+        return;
+    }
+
+    let username = username.trim().to_string();
+    if username.is_empty() || username.chars().count() > MAX_USERNAME_LENGTH {
+        registration_failed(
+            handle_data,
+            &format!(
+                "Usernames have to be between 1 and {} characters",
+                MAX_USERNAME_LENGTH
+            ),
+        );
+        return;
+    }
+
+    let min_password_length = handle_data.config.accounts.min_password_length;
+    if password.chars().count() < min_password_length {
+        registration_failed(
+            handle_data,
+            &format!(
+                "Passwords have to be at least {} characters",
+                min_password_length
+            ),
+        );
+        return;
+    }
+
+    // The code is only used up if the account is made
+    let txn = handle_data
+        .db_clone
+        .begin()
+        .await
+        .expect("Failed to start transaction");
+
+    // Use up one of the code's uses, if it has any left. Doing it in one
+    // update means two people can't both get its last use.
+    let used = invite_code::Entity::update_many()
+        .col_expr(
+            invite_code::Column::UsesLeft,
+            Expr::col(invite_code::Column::UsesLeft).sub(1),
+        )
+        .filter(invite_code::Column::Code.eq(&invite_code))
+        .filter(invite_code::Column::UsesLeft.gt(0))
+        .exec(&txn)
+        .await
+        .expect("Failed to use invite code");
+    if used.rows_affected == 0 {
+        registration_failed(handle_data, "That invite code doesn't work");
+        return;
+    }
+
+    // Organizers hand out codes that make whoever registers with them an
+    // organizer or admin
+    let role = invite_code::Entity::find_by_id(invite_code)
+        .one(&txn)
+        .await
+        .expect("Failed to get invite code")
+        .map_or(Role::Hacker, |invite_code| invite_code.role);
+
+    // Hashing is slow, so it's only done for a code that works
+    let password_hash = accounts::hash_password(password).await;
+
+    // Nobody can take the name of someone else, even if they came from
+    // Discord, since it's all anyone sees on the scoreboard
+    let hacker = insert_hacker(
+        &txn,
+        hacker::ActiveModel {
+            username: Set(username.clone()),
+            password_hash: Set(Some(password_hash)),
+            role: Set(role),
+            ..Default::default()
+        },
+    )
+    .await
+    .expect("Failed to create hacker");
+    let hacker = match hacker {
+        Some(hacker) => hacker,
+        None => {
+            registration_failed(handle_data, &format!("{} is already taken", username));
+            return;
+        }
+    };
+
+    txn.commit().await.expect("Failed to create hacker");
+
+    let token = new_session_token(
+        hacker.id,
+        &handle_data.config.sessions,
+        &handle_data.db_clone,
+    )
+    .await;
+
+    authenticate(handle_data, hacker, token).await;
+}
+
+fn registration_failed(handle_data: &HandleData, reason: &str) {
+    CTFServer::send_message_associated(
+        ServerMessage::ClientUpdate(ClientUpdate::RegistrationFailed(reason.to_string())),
+        handle_data.recipient.clone(),
+    );
+}
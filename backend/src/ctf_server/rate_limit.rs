@@ -0,0 +1,356 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use common::ctf_message::{HackerId, TeamId};
+use config::{BucketConfig, RateLimitConfig};
+
+/// Token buckets that limit how often flags can be submitted. A submission
+/// takes a token from the hacker's bucket before anything else is looked up,
+/// then from their team's bucket and their team's bucket for that challenge.
+/// If any of them is empty, the submission is turned away and that bucket is
+/// locked for a while. The lock gets longer
+/// each time the bucket runs dry again before it has had a chance to refill,
+/// so brute forcing a flag gets slower and slower.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: HashMap<BucketKey, Bucket>,
+    last_prune: Instant,
+}
+
+/// How often buckets that have refilled are thrown away, so there isn't one
+/// kept around for every challenge every team has ever tried
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum BucketKey {
+    Hacker(HackerId),
+    Team(TeamId),
+    Challenge { team: TeamId, challenge: i32 },
+}
+
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+    /// How many times in a row this bucket has run out
+    strikes: u32,
+    locked_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        RateLimiter {
+            config,
+            buckets: HashMap::new(),
+            last_prune: Instant::now(),
+        }
+    }
+
+    /// Take a submission from the hacker's bucket. This only needs who they
+    /// are, so it's checked before the submission costs anything. If it isn't
+    /// allowed, returns how long until they can try again.
+    pub fn check_hacker(&mut self, hacker: HackerId) -> Result<(), Duration> {
+        self.take(&[BucketKey::Hacker(hacker)], Instant::now())
+    }
+
+    /// Take a submission from the team's bucket, and the team's bucket for
+    /// the challenge
+    pub fn check_team(&mut self, team: TeamId, challenge: i32) -> Result<(), Duration> {
+        self.take(
+            &[
+                BucketKey::Team(team),
+                BucketKey::Challenge { team, challenge },
+            ],
+            Instant::now(),
+        )
+    }
+
+    /// Both checks, in the order a submission goes through them
+    #[cfg(test)]
+    fn check_at(
+        &mut self,
+        hacker: HackerId,
+        team: TeamId,
+        challenge: i32,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        self.take(&[BucketKey::Hacker(hacker)], now)?;
+        self.take(
+            &[
+                BucketKey::Team(team),
+                BucketKey::Challenge { team, challenge },
+            ],
+            now,
+        )
+    }
+
+    /// Take a token from every one of these buckets, or from none of them if
+    /// any is locked or empty
+    fn take(&mut self, keys: &[BucketKey], now: Instant) -> Result<(), Duration> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        if now.duration_since(self.last_prune) >= PRUNE_INTERVAL {
+            self.prune(now);
+        }
+
+        // Top up every bucket, and find the longest wait if any of them are
+        // locked or empty
+        let mut retry_after = None;
+        for &key in keys {
+            let bucket_config = bucket_config(&self.config, key).clone();
+            let bucket = self.buckets.entry(key).or_insert_with(|| Bucket {
+                tokens: bucket_config.capacity as f64,
+                last_refill: now,
+                strikes: 0,
+                locked_until: None,
+            });
+
+            bucket.refill(&bucket_config, now);
+
+            let wait = match bucket.locked_until {
+                Some(locked_until) if locked_until > now => Some(locked_until - now),
+                Some(_) => {
+                    // Sitting out the cooldown earns another try
+                    bucket.locked_until = None;
+                    bucket.tokens = bucket.tokens.max(1.0);
+                    None
+                }
+                None if bucket.tokens < 1.0 => Some(bucket.lock(&self.config, now)),
+                None => None,
+            };
+
+            retry_after = retry_after.max(wait);
+        }
+
+        if let Some(retry_after) = retry_after {
+            return Err(retry_after);
+        }
+
+        // Everything has room, so this submission counts against all of them
+        for key in keys {
+            if let Some(bucket) = self.buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Forget every bucket that's no different from a new one
+    fn prune(&mut self, now: Instant) {
+        let config = &self.config;
+        self.buckets
+            .retain(|key, bucket| !bucket.is_idle(bucket_config(config, *key), now));
+        self.last_prune = now;
+    }
+}
+
+fn bucket_config(config: &RateLimitConfig, key: BucketKey) -> &BucketConfig {
+    match key {
+        BucketKey::Hacker(_) => &config.hacker,
+        BucketKey::Team(_) => &config.team,
+        BucketKey::Challenge { .. } => &config.challenge,
+    }
+}
+
+impl Bucket {
+    fn refill(&mut self, config: &BucketConfig, now: Instant) {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * config.refill_per_minute / 60.0).min(config.capacity as f64);
+        self.last_refill = now;
+
+        // Once it has completely refilled, it's forgiven for running out
+        let locked = matches!(self.locked_until, Some(locked_until) if locked_until > now);
+        if !locked && self.tokens >= config.capacity as f64 {
+            self.strikes = 0;
+            self.locked_until = None;
+        }
+    }
+
+    /// Whether the bucket would be full by now and isn't locked, so nothing is
+    /// held against whoever it belongs to
+    fn is_idle(&self, config: &BucketConfig, now: Instant) -> bool {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        let full =
+            self.tokens + elapsed * config.refill_per_minute / 60.0 >= config.capacity as f64;
+
+        full && !matches!(self.locked_until, Some(locked_until) if locked_until > now)
+    }
+
+    /// Lock the bucket for longer each time it runs out, and return how long
+    /// it's locked for
+    fn lock(&mut self, config: &RateLimitConfig, now: Instant) -> Duration {
+        let cooldown = config
+            .base_cooldown_secs
+            .saturating_mul(2u64.saturating_pow(self.strikes))
+            .min(config.max_cooldown_secs);
+
+        self.strikes += 1;
+
+        let cooldown = Duration::from_secs(cooldown);
+        self.locked_until = Some(now + cooldown);
+        cooldown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            hacker: BucketConfig {
+                capacity: 100,
+                refill_per_minute: 100.0,
+            },
+            team: BucketConfig {
+                capacity: 100,
+                refill_per_minute: 100.0,
+            },
+            // A token every 100 seconds
+            challenge: BucketConfig {
+                capacity: 3,
+                refill_per_minute: 0.6,
+            },
+            base_cooldown_secs: 10,
+            max_cooldown_secs: 35,
+        })
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn allows_a_burst_then_locks() {
+        let mut limiter = limiter();
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at(1, 1, 1, now), Ok(()));
+        }
+        assert_eq!(limiter.check_at(1, 1, 1, now), Err(secs(10)));
+
+        // Still locked, and the wait counts down
+        assert_eq!(limiter.check_at(1, 1, 1, now + secs(4)), Err(secs(6)));
+    }
+
+    #[test]
+    fn cooldowns_double_up_to_the_maximum() {
+        let mut limiter = limiter();
+        let mut now = Instant::now();
+
+        for _ in 0..3 {
+            limiter.check_at(1, 1, 1, now).unwrap();
+        }
+
+        // Each lockout only earns one more try before the next, longer one
+        for cooldown in [10, 20, 35, 35] {
+            assert_eq!(limiter.check_at(1, 1, 1, now), Err(secs(cooldown)));
+            now += secs(cooldown);
+            assert_eq!(limiter.check_at(1, 1, 1, now), Ok(()));
+        }
+    }
+
+    #[test]
+    fn refilling_completely_forgives_running_out() {
+        let mut limiter = limiter();
+        let mut now = Instant::now();
+
+        for _ in 0..3 {
+            limiter.check_at(1, 1, 1, now).unwrap();
+        }
+        assert_eq!(limiter.check_at(1, 1, 1, now), Err(secs(10)));
+
+        // Sitting out the cooldown and then some gives back all 3 tokens
+        now += secs(300);
+        for _ in 0..3 {
+            assert_eq!(limiter.check_at(1, 1, 1, now), Ok(()));
+        }
+        assert_eq!(limiter.check_at(1, 1, 1, now), Err(secs(10)));
+    }
+
+    #[test]
+    fn challenges_and_teams_have_their_own_buckets() {
+        let mut limiter = limiter();
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            limiter.check_at(1, 1, 1, now).unwrap();
+        }
+        assert!(limiter.check_at(1, 1, 1, now).is_err());
+
+        // Another challenge, or the same challenge for another team, is fine
+        assert_eq!(limiter.check_at(1, 1, 2, now), Ok(()));
+        assert_eq!(limiter.check_at(2, 2, 1, now), Ok(()));
+
+        // A teammate is held up by the team's bucket for that challenge
+        assert!(limiter.check_at(3, 1, 1, now).is_err());
+    }
+
+    #[test]
+    fn hackers_are_stopped_before_their_team_is_known() {
+        let mut limiter = limiter();
+        limiter.config.hacker.capacity = 2;
+
+        for _ in 0..2 {
+            assert_eq!(limiter.check_hacker(1), Ok(()));
+        }
+        assert_eq!(limiter.check_hacker(1), Err(secs(10)));
+        assert_eq!(limiter.buckets.len(), 1);
+
+        // Their teammates can still submit
+        assert_eq!(limiter.check_hacker(2), Ok(()));
+    }
+
+    #[test]
+    fn turned_off_allows_everything() {
+        let mut limiter = limiter();
+        limiter.config.enabled = false;
+        let now = Instant::now();
+
+        for _ in 0..100 {
+            assert_eq!(limiter.check_at(1, 1, 1, now), Ok(()));
+        }
+    }
+
+    #[test]
+    fn idle_buckets_are_pruned() {
+        let mut limiter = limiter();
+        let start = limiter.last_prune;
+
+        limiter.check_at(1, 1, 1, start).unwrap();
+        for _ in 0..3 {
+            limiter.check_at(2, 2, 2, start).unwrap();
+        }
+        assert!(limiter.check_at(2, 2, 2, start).is_err());
+        assert_eq!(limiter.buckets.len(), 6);
+
+        // Two minutes later, everything but the second hacker's challenge
+        // bucket has refilled
+        limiter.check_at(3, 3, 3, start + secs(120)).unwrap();
+
+        let mut kept = limiter.buckets.keys().copied().collect::<Vec<_>>();
+        kept.sort_by_key(|key| format!("{:?}", key));
+        assert_eq!(
+            kept,
+            vec![
+                BucketKey::Challenge {
+                    team: 2,
+                    challenge: 2
+                },
+                BucketKey::Challenge {
+                    team: 3,
+                    challenge: 3
+                },
+                BucketKey::Hacker(3),
+                BucketKey::Team(3),
+            ]
+        );
+    }
+}
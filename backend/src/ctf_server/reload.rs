@@ -0,0 +1,69 @@
+//! Loading the challenges again while the CTF is running. Organizers can ask
+//! for a reload, and the server can also check the repo on its own every
+//! `repo.reload_poll_secs`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use actix_web::web;
+use anyhow::{bail, Context};
+use common::ctf_message::CTFState;
+use config::RepoConfig;
+use sea_orm::DatabaseConnection;
+
+use crate::repo::{Changes, Repo};
+
+use super::{handlers::queue_game_data, ActorTask};
+
+/// Only one reload runs at a time, since each one replaces the repo folder
+static RELOADING: AtomicBool = AtomicBool::new(false);
+
+/// Lets the next reload run once this one is done, even if it fails
+struct ReloadGuard;
+
+impl Drop for ReloadGuard {
+    fn drop(&mut self) {
+        RELOADING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Get the repo again and apply whatever changed to the database. If anything
+/// did, everyone is sent the new scoreboard and every hacker is sent the new
+/// challenge list.
+pub async fn reload_challenges(
+    repo_config: &RepoConfig,
+    db: &DatabaseConnection,
+    tasks: &mut Vec<ActorTask>,
+) -> anyhow::Result<Changes> {
+    let repo = refetch_repo(repo_config).await?;
+    let changes = repo
+        .update_database(db)
+        .await
+        .context("Failed to save the challenges")?;
+
+    if changes.is_empty() {
+        return Ok(changes);
+    }
+
+    // Point values might have changed, and so might the whole scoreboard
+    tasks.push(ActorTask::ReplaceGlobalData(
+        CTFState::get_global_data(db).await,
+    ));
+
+    // Every team sees the challenges a bit differently, so each one gets
+    // their own list
+    queue_game_data(db, tasks).await;
+
+    Ok(changes)
+}
+
+/// Get and parse the challenges on the blocking thread pool, so the server can
+/// keep handling messages while a clone is running
+pub async fn refetch_repo(repo_config: &RepoConfig) -> anyhow::Result<Repo> {
+    if RELOADING.swap(true, Ordering::SeqCst) {
+        bail!("The challenges are already being reloaded");
+    }
+    let _guard = ReloadGuard;
+
+    let repo_config = repo_config.clone();
+    web::block(move || Repo::refetch_repo(&repo_config)).await?
+}
@@ -0,0 +1,38 @@
+use config::EventConfig;
+use entity::{entities::event, helpers::get_event};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+
+/// Store the event schedule from the config in the database, so that it can be
+/// sent to clients and checked against submissions. If the config doesn't have
+/// a schedule, whatever is already in the database is left alone.
+pub async fn update_database(event_config: &EventConfig, db: &DatabaseConnection) {
+    let (start, end) = match (event_config.start, event_config.end) {
+        (Some(start), Some(end)) => (start.naive_utc(), end.naive_utc()),
+        _ => return,
+    };
+    let freeze = event_config.freeze.map(|freeze| freeze.naive_utc());
+
+    match get_event(db).await {
+        // There's only ever one event, so update it if it exists
+        Some(db_event) => {
+            let mut db_event: event::ActiveModel = db_event.into();
+
+            db_event.start = Set(start);
+            db_event.end = Set(end);
+            db_event.freeze = Set(freeze);
+
+            db_event.update(db).await.unwrap();
+        }
+        None => {
+            event::ActiveModel {
+                start: Set(start),
+                end: Set(end),
+                freeze: Set(freeze),
+                ..Default::default()
+            }
+            .insert(db)
+            .await
+            .unwrap();
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Downloads for the files attached to challenges. The client asks for a file
+//! over its websocket and is sent a download token for just that file, which
+//! works once and only for a minute. It goes in the link as
+//! `?download=<token>`, so a plain link works from the browser without the
+//! session token ending up in server logs or the browser's history.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    get,
+    http::header,
+    web::{Data, Path, Query},
+    HttpResponse,
+};
+use common::{
+    ctf_message::{ChallengeId, HackerId},
+    unlocks::get_unlocked_challenges,
+};
+use entity::entities::{blob, challenge, challenge_file, hacker};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, QueryFilter};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// How long a download token works for
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Download tokens that have been handed out but not used yet
+#[derive(Default)]
+pub struct Downloads(Mutex<HashMap<String, Download>>);
+
+struct Download {
+    hacker_id: HackerId,
+    challenge_id: ChallengeId,
+    name: String,
+    started: Instant,
+}
+
+impl Downloads {
+    /// Make a token for a hacker to download one file with
+    pub fn start(&self, hacker_id: HackerId, challenge_id: ChallengeId, name: String) -> String {
+        let token = Uuid::new_v4().as_simple().to_string();
+
+        let mut downloads = self.0.lock().unwrap();
+        downloads.retain(|_, download| download.started.elapsed() < DOWNLOAD_TIMEOUT);
+        downloads.insert(
+            token.clone(),
+            Download {
+                hacker_id,
+                challenge_id,
+                name,
+                started: Instant::now(),
+            },
+        );
+
+        token
+    }
+
+    /// The hacker this token was made for, if it's for this file and hasn't
+    /// expired. Each token can only be used once.
+    fn finish(&self, token: &str, challenge_id: ChallengeId, name: &str) -> Option<HackerId> {
+        self.0
+            .lock()
+            .unwrap()
+            .remove(token)
+            .filter(|download| download.started.elapsed() < DOWNLOAD_TIMEOUT)
+            .filter(|download| download.challenge_id == challenge_id && download.name == name)
+            .map(|download| download.hacker_id)
+    }
+}
+
+/// Why a hacker can't have a file
+#[derive(Debug)]
+pub enum FileError {
+    NotOnTeam,
+    NoChallenge,
+    Locked,
+    NoFile,
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FileError::NotOnTeam => "You need to be on a team to download files",
+            FileError::NoChallenge => "That challenge doesn't exist",
+            FileError::Locked => "Your team hasn't unlocked this challenge yet",
+            FileError::NoFile => "That file doesn't exist",
+        })
+    }
+}
+
+impl FileError {
+    fn response(&self) -> HttpResponse {
+        match self {
+            FileError::NotOnTeam | FileError::Locked => HttpResponse::Forbidden(),
+            FileError::NoChallenge | FileError::NoFile => HttpResponse::NotFound(),
+        }
+        .body(self.to_string())
+    }
+}
+
+/// One of a challenge's files, if the hacker is allowed to download it. Only
+/// hackers on a team can download them, and only for active challenges their
+/// team has unlocked that haven't been retired.
+pub async fn find_file(
+    db: &DatabaseConnection,
+    hacker_id: HackerId,
+    challenge_id: ChallengeId,
+    name: &str,
+) -> Result<challenge_file::Model, FileError> {
+    let team_id = hacker::Entity::find_by_id(hacker_id)
+        .one(db)
+        .await
+        .expect("Failed to get hacker")
+        .and_then(|hacker| hacker.fk_team_id)
+        .ok_or(FileError::NotOnTeam)?;
+
+    // Inactive and retired challenges are treated as if they don't exist
+    let challenge = challenge::Entity::find_by_id(challenge_id)
+        .filter(challenge::Column::Active.eq(true))
+        .filter(challenge::Column::Retired.eq(false))
+        .one(db)
+        .await
+        .expect("Failed to get challenge")
+        .ok_or(FileError::NoChallenge)?;
+
+    if !get_unlocked_challenges(Some(team_id), db)
+        .await
+        .contains(&challenge.id)
+    {
+        return Err(FileError::Locked);
+    }
+
+    challenge
+        .find_related(challenge_file::Entity)
+        .filter(challenge_file::Column::Name.eq(name))
+        .one(db)
+        .await
+        .expect("Failed to get challenge file")
+        .ok_or(FileError::NoFile)
+}
+
+#[derive(Deserialize)]
+pub struct FileQuery {
+    download: String,
+}
+
+/// One of a challenge's files, for whoever was given the download token
+#[get("/files/{challenge}/{name:.*}")]
+pub async fn challenge_file_route(
+    path: Path<(ChallengeId, String)>,
+    query: Query<FileQuery>,
+    db: Data<DatabaseConnection>,
+    downloads: Data<Downloads>,
+) -> HttpResponse {
+    let (challenge_id, name) = path.into_inner();
+    let db = db.get_ref();
+
+    let Some(hacker_id) = downloads.finish(&query.download, challenge_id, &name) else {
+        return HttpResponse::Unauthorized().body("That download link expired, try again");
+    };
+
+    // Their team might have changed since they asked for it
+    let file = match find_file(db, hacker_id, challenge_id, &name).await {
+        Ok(file) => file,
+        Err(e) => return e.response(),
+    };
+
+    let blob = match file
+        .find_related(blob::Entity)
+        .one(db)
+        .await
+        .expect("Failed to get file contents")
+    {
+        Some(blob) => blob,
+        None => {
+            log::error!("Challenge file {} has no contents", file.id);
+            return HttpResponse::InternalServerError().body("That file is missing");
+        }
+    };
+
+    // Files in folders are saved with just their own name
+    let file_name = name.rsplit('/').next().unwrap_or(&name);
+
+    HttpResponse::Ok()
+        .content_type("application/octet-stream")
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", file_name.replace('"', "")),
+        ))
+        .insert_header((header::ETAG, format!("\"{}\"", blob.hash)))
+        .body(blob.data)
+}
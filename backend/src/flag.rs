@@ -0,0 +1,211 @@
+//! Checking submitted flags against the flags a challenge accepts.
+//!
+//! A challenge can accept any number of flags, and each one is compared using
+//! its own match mode:
+//! - `exact`: the submission has to be exactly the same
+//! - `case_insensitive`: the same, ignoring upper and lower case
+//! - `trimmed`: the same, ignoring whitespace at the start and end
+//! - `regex`: the whole submission has to match the regular expression
+//! - `per_team`: the flag is a template, and every team has to submit their
+//!   own flag made from it. See [`team_flag`].
+
+use anyhow::{bail, Context};
+use entity::entities::{challenge_flag, sea_orm_active_enums::FlagMatchMode};
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use sha2::Sha256;
+
+/// Where a team's hash goes in a per-team flag template
+pub const TEAM_FLAG_PLACEHOLDER: &str = "{hmac}";
+
+/// Whether the submitted flag matches any of the challenge's accepted flags.
+/// Per-team flags need the server's flag secret, and never match without it.
+pub fn any_matches(
+    flags: &[challenge_flag::Model],
+    submitted: &str,
+    team_id: i32,
+    secret: Option<&str>,
+) -> bool {
+    flags.iter().any(|flag| match (flag.match_mode, secret) {
+        (FlagMatchMode::PerTeam, Some(secret)) => {
+            team_flag(secret, &flag.flag, team_id, flag.fk_challenge_id) == submitted
+        }
+        (FlagMatchMode::PerTeam, None) => {
+            log::warn!(
+                "Challenge {} has a per-team flag, but there's no flag secret set",
+                flag.fk_challenge_id
+            );
+            false
+        }
+        (mode, _) => matches(&flag.flag, mode, submitted),
+    })
+}
+
+/// Whether the submitted flag matches an accepted flag. A regex that doesn't
+/// compile never matches, so it should be caught with [`validate`] when the
+/// challenge is loaded. Per-team flags depend on who submitted them, so they
+/// are checked by [`any_matches`] instead.
+pub fn matches(expected: &str, mode: FlagMatchMode, submitted: &str) -> bool {
+    match mode {
+        FlagMatchMode::Exact => expected == submitted,
+        FlagMatchMode::CaseInsensitive => expected.to_lowercase() == submitted.to_lowercase(),
+        FlagMatchMode::Trimmed => expected.trim() == submitted.trim(),
+        FlagMatchMode::Regex => match anchored_regex(expected) {
+            Ok(regex) => regex.is_match(submitted),
+            Err(e) => {
+                log::warn!("Flag regex {:?} doesn't compile: {}", expected, e);
+                false
+            }
+        },
+        FlagMatchMode::PerTeam => false,
+    }
+}
+
+/// Make sure an accepted flag can be used with its match mode
+pub fn validate(expected: &str, mode: FlagMatchMode) -> anyhow::Result<()> {
+    match mode {
+        FlagMatchMode::Regex => {
+            anchored_regex(expected)
+                .with_context(|| format!("{:?} isn't a valid regex", expected))?;
+        }
+        FlagMatchMode::PerTeam if expected.matches(TEAM_FLAG_PLACEHOLDER).count() != 1 => {
+            bail!(
+                "Per-team flag {:?} needs exactly one {} in it",
+                expected,
+                TEAM_FLAG_PLACEHOLDER
+            );
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
+/// The flag a team has to submit for a per-team flag. The placeholder in the
+/// template is replaced with an HMAC of the team and challenge, keyed with the
+/// server's flag secret, so teams can't work out each other's flags.
+pub fn team_flag(secret: &str, template: &str, team_id: i32, challenge_id: i32) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take a key of any size");
+    mac.update(format!("{}:{}", team_id, challenge_id).as_bytes());
+
+    // Half of the hash is plenty, and keeps the flag short enough to type
+    let hash = mac.finalize().into_bytes();
+
+    template.replacen(TEAM_FLAG_PLACEHOLDER, &hex::encode(&hash[..16]), 1)
+}
+
+/// Regex flags have to match the whole submission, not just part of it
+fn anchored_regex(expected: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})$", expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "super secret";
+
+    fn flag(flag: &str, match_mode: FlagMatchMode) -> challenge_flag::Model {
+        challenge_flag::Model {
+            id: 0,
+            flag: flag.to_string(),
+            match_mode,
+            fk_challenge_id: 7,
+        }
+    }
+
+    #[test]
+    fn exact() {
+        assert!(matches("h4tt{flag}", FlagMatchMode::Exact, "h4tt{flag}"));
+        assert!(!matches("h4tt{flag}", FlagMatchMode::Exact, "H4TT{FLAG}"));
+        assert!(!matches("h4tt{flag}", FlagMatchMode::Exact, " h4tt{flag}"));
+        assert!(!matches("h4tt{flag}", FlagMatchMode::Exact, "flag"));
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let mode = FlagMatchMode::CaseInsensitive;
+        assert!(matches("h4tt{flag}", mode, "H4TT{Flag}"));
+        assert!(!matches("h4tt{flag}", mode, "h4tt{flag} "));
+    }
+
+    #[test]
+    fn trimmed() {
+        let mode = FlagMatchMode::Trimmed;
+        assert!(matches("h4tt{flag}", mode, "  h4tt{flag}\n"));
+        assert!(!matches("h4tt{flag}", mode, "h4tt{ flag }"));
+        assert!(!matches("h4tt{flag}", mode, "H4TT{FLAG}"));
+    }
+
+    #[test]
+    fn regex_matches_whole_submission() {
+        let mode = FlagMatchMode::Regex;
+        assert!(matches(r"h4tt\{[0-9a-f]{4}\}", mode, "h4tt{beef}"));
+        assert!(!matches(r"h4tt\{[0-9a-f]{4}\}", mode, "h4tt{beefy}"));
+        assert!(!matches(r"h4tt\{[0-9a-f]{4}\}", mode, "xh4tt{beef}"));
+        assert!(!matches(r"h4tt\{[0-9a-f]{4}\}", mode, "h4tt{beef}x"));
+
+        // Alternations are anchored as a group
+        assert!(matches("a|b", mode, "a"));
+        assert!(!matches("a|b", mode, "ab"));
+    }
+
+    #[test]
+    fn invalid_regex_never_matches() {
+        assert!(validate("h4tt{(", FlagMatchMode::Regex).is_err());
+        assert!(!matches("h4tt{(", FlagMatchMode::Regex, "h4tt{("));
+
+        // Other modes don't care what the flag looks like
+        assert!(validate("h4tt{(", FlagMatchMode::Exact).is_ok());
+    }
+
+    #[test]
+    fn any_of_several_flags() {
+        let flags = vec![
+            flag("h4tt{first}", FlagMatchMode::Exact),
+            flag("h4tt{second}", FlagMatchMode::CaseInsensitive),
+        ];
+
+        assert!(any_matches(&flags, "h4tt{first}", 1, None));
+        assert!(any_matches(&flags, "H4TT{SECOND}", 1, None));
+        assert!(!any_matches(&flags, "H4TT{FIRST}", 1, None));
+        assert!(!any_matches(&flags, "flag", 1, None));
+        assert!(!any_matches(&[], "flag", 1, None));
+    }
+
+    #[test]
+    fn team_flags_are_different_for_every_team_and_challenge() {
+        let team_1 = team_flag(SECRET, "h4tt{{hmac}}", 1, 7);
+
+        assert!(team_1.starts_with("h4tt{") && team_1.ends_with('}'));
+        assert_eq!(team_1.len(), "h4tt{}".len() + 32);
+
+        // The same inputs always give the same flag
+        assert_eq!(team_1, team_flag(SECRET, "h4tt{{hmac}}", 1, 7));
+
+        assert_ne!(team_1, team_flag(SECRET, "h4tt{{hmac}}", 2, 7));
+        assert_ne!(team_1, team_flag(SECRET, "h4tt{{hmac}}", 1, 8));
+        assert_ne!(team_1, team_flag("another secret", "h4tt{{hmac}}", 1, 7));
+    }
+
+    #[test]
+    fn per_team_flag_only_solves_for_its_team() {
+        let flags = vec![flag("h4tt{{hmac}}", FlagMatchMode::PerTeam)];
+        let team_1 = team_flag(SECRET, "h4tt{{hmac}}", 1, 7);
+
+        assert!(any_matches(&flags, &team_1, 1, Some(SECRET)));
+        assert!(!any_matches(&flags, &team_1, 2, Some(SECRET)));
+        assert!(!any_matches(&flags, "h4tt{{hmac}}", 1, Some(SECRET)));
+
+        // Without a secret, nothing can solve it
+        assert!(!any_matches(&flags, &team_1, 1, None));
+    }
+
+    #[test]
+    fn per_team_template_needs_one_placeholder() {
+        assert!(validate("h4tt{{hmac}}", FlagMatchMode::PerTeam).is_ok());
+        assert!(validate("h4tt{static}", FlagMatchMode::PerTeam).is_err());
+        assert!(validate("{hmac}{hmac}", FlagMatchMode::PerTeam).is_err());
+    }
+}
@@ -0,0 +1,5 @@
+//! The parts of the backend that other binaries need too, like `ctf-lint`
+//! checking a challenge repo without starting the server.
+
+pub mod flag;
+pub mod repo;
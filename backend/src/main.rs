@@ -1,43 +1,63 @@
 use actix::Actor;
 use actix_web::{middleware::Logger, web::Data, App, HttpServer};
 
+use api::{challenge_flags_route, challenges_route, team_flags_route};
+use config::Config;
 use ctf_server::CTFServer;
+use files::challenge_file_route;
+use oauth::{oauth_callback_route, oauth_login_route, PendingLogins};
 
+use backend::{flag, repo};
 use repo::Repo;
 use start_connection::start_connection_route;
 
+mod accounts;
+mod api;
 mod ctf_server;
+mod event;
+mod files;
 mod messages;
-mod repo;
+mod oauth;
 mod start_connection;
 mod ws_conn;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    // Try connecting to the database again once every 5 seconds if it fails
-    let ctf_server = {
+    // Load the config before anything else, and stop right away if it's not
+    // valid
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Try connecting to the database again every few seconds if it fails
+    let mut ctf_server = {
         loop {
-            let result = CTFServer::new_with_rooms().await;
+            let result = CTFServer::new_with_rooms(config.clone()).await;
 
             match result {
                 Ok(ctf_server) => break ctf_server,
                 Err(e) => {
                     println!("Failed to connect to database: {}", e);
-                    std::thread::sleep(std::time::Duration::from_secs(5));
+                    std::thread::sleep(std::time::Duration::from_secs(
+                        config.database.connect_retry_secs,
+                    ));
                 }
             }
         }
     };
 
+    let db = ctf_server.db.clone();
+
     // // Reset the database /shrug
     // Migrator::fresh(&ctf_server.db).await.unwrap();
 
     // Run database migrations
     // Migrator::up(&ctf_server.db, None).await.unwrap();
 
-    // Create the CTF server actor
-    let ctf_server = Data::new(ctf_server.start());
-
     // env_logger::init_from_env(env_logger::Env::new().default_filter_or("debug"));
     // start an env logger like above, but ignore sqlx queries
 
@@ -45,22 +65,64 @@ async fn main() -> std::io::Result<()> {
         .filter_module("sqlx", log::LevelFilter::Off)
         .init();
 
-    // Download the repo
-    Repo::clone_repo();
+    // Get the challenges from wherever they're kept
+    if let Err(e) = Repo::fetch_repo(&config.repo) {
+        eprintln!("Failed to load the challenges: {:#}", e);
+        std::process::exit(1);
+    }
 
     // Load the repo from the repository
-    let repo = Repo::parse_repo();
+    let repo = match Repo::parse_repo(&config.repo) {
+        Ok(repo) => repo,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+    };
 
     // Load all the challenges found into the database
-    repo.update_database().await;
+    match repo.update_database(&db).await {
+        Ok(changes) => println!("Loaded the challenges: {}", changes),
+        Err(e) => {
+            eprintln!("Failed to save the challenges: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // Load the event schedule into the database
+    event::update_database(&config.event, &db).await;
+
+    // Load the invite codes for password accounts into the database
+    accounts::update_database(&config.accounts, &db).await;
+
+    // Create the CTF server actor, now the scoreboard it starts with has
+    // everything in it
+    ctf_server.load_global_data().await;
+    let downloads = Data::from(ctf_server.downloads.clone());
+    let ctf_server = Data::new(ctf_server.start());
+
+    let bind_address = config.server.bind_address;
+    let config = Data::new(config);
+    let db = Data::new(db);
+    let pending_logins = Data::new(PendingLogins::default());
 
     HttpServer::new(move || {
         App::new()
             .service(start_connection_route)
+            .service(challenges_route)
+            .service(challenge_flags_route)
+            .service(team_flags_route)
+            .service(challenge_file_route)
+            .service(oauth_login_route)
+            .service(oauth_callback_route)
             .app_data(ctf_server.clone())
+            .app_data(config.clone())
+            .app_data(db.clone())
+            .app_data(pending_logins.clone())
+            .app_data(downloads.clone())
             .wrap(Logger::default())
     })
-    .bind("0.0.0.0:4040")?
+    .bind(bind_address)?
     .run()
     .await
 }
@@ -1,6 +1,6 @@
 use actix::prelude::{Message, Recipient};
 
-use common::{ctf_message::{CTFMessage, DiscordClientId}, ClientId, NetworkMessage};
+use common::{ctf_message::{ClientMessage, HackerId, ServerMessage}, ClientId};
 
 // The response type returned by the actor future
 pub type OriginalActorResponse = ();
@@ -27,9 +27,9 @@ pub struct Disconnect {
 #[rtype(result = "()")]
 pub enum WsActorMessage {
     /// Messages incoming from the client
-    IncomingMessage(NetworkMessage),
+    IncomingMessage(ServerMessage),
     /// Messages that should get passed right along to the client
-    OutgoingMessage(NetworkMessage),
+    OutgoingMessage(ServerMessage),
     /// Messages that should be handled by the client's websocket actor
     ActorRequest(ActorRequest),
 }
@@ -38,7 +38,7 @@ pub enum WsActorMessage {
 #[rtype(result = "()")]
 pub struct CTFRoomMessage {
     pub id: ClientId,
-    pub ctf_message: CTFMessage,
+    pub ctf_message: ClientMessage,
 }
 
 #[derive(Message)]
@@ -49,12 +49,12 @@ pub enum ActorRequest {}
 #[rtype(result = "Result<(), MessageError>")]
 pub struct IncomingCTFRequest {
     pub id: ClientId,
-    pub ctf_message: CTFMessage,
+    pub ctf_message: ClientMessage,
 }
 
 #[derive(Message, Clone)]
 #[rtype(result = "(Result<(), MessageError>)")]
 pub struct AnonymousCTFRequest {
-    pub ctf_message: CTFMessage,
-    pub discord_id: DiscordClientId,
+    pub ctf_message: ClientMessage,
+    pub hacker_id: HackerId,
 }
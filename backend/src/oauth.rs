@@ -0,0 +1,629 @@
+//! Logging in through an OAuth2 provider with the authorization code flow.
+//! `/oauth/login` sends the player to the provider, which sends them back to
+//! `/oauth/callback` with a code. The code is traded for the player's id and
+//! name, which are linked to a hacker, and the player is sent back to the
+//! frontend with a session token as `#token=<token>`.
+//!
+//! The `state` sent to the provider is also kept in a cookie, so a login can
+//! only be finished by the browser that started it. Otherwise someone could
+//! send their own half-finished login to another player, and log them in as
+//! the wrong hacker.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use actix_web::{
+    cookie::{time, Cookie, SameSite},
+    get,
+    http::header,
+    web::{Data, Query},
+    HttpRequest, HttpResponse,
+};
+use anyhow::{anyhow, bail, Context};
+use config::{Config, OAuthConfig};
+use entity::{
+    entities::{hacker, oauth_identity},
+    helpers::insert_hacker_with_free_username,
+};
+use reqwest::Url;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, ModelTrait,
+    QueryFilter, Set,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::ctf_server::handlers::unauthenticated_login::new_session_token;
+
+/// How long a player has to log in to the provider before they have to start
+/// over
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// The cookie that ties a login's `state` to the browser that started it
+const STATE_COOKIE: &str = "oauth_state";
+
+/// The `state` of every login that's been started but hasn't come back from
+/// the provider yet. A callback without one of these didn't start here.
+#[derive(Default)]
+pub struct PendingLogins(Mutex<HashMap<String, Instant>>);
+
+impl PendingLogins {
+    fn start(&self) -> String {
+        let state = Uuid::new_v4().as_simple().to_string();
+
+        let mut pending = self.0.lock().unwrap();
+        pending.retain(|_, started| started.elapsed() < LOGIN_TIMEOUT);
+        pending.insert(state.clone(), Instant::now());
+
+        state
+    }
+
+    /// Whether this login was started here recently. Each one can only be
+    /// finished once.
+    fn finish(&self, state: &str) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .remove(state)
+            .is_some_and(|started| started.elapsed() < LOGIN_TIMEOUT)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CallbackQuery {
+    code: Option<String>,
+    /// Some providers leave this out when they send back an error
+    state: Option<String>,
+    /// Set by the provider instead of a code if the player didn't allow it
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+/// Send the player to the provider to log in
+#[get("/oauth/login")]
+pub async fn oauth_login_route(
+    config: Data<Config>,
+    pending_logins: Data<PendingLogins>,
+) -> HttpResponse {
+    let oauth = &config.oauth;
+    if !oauth.enabled {
+        return HttpResponse::NotFound().body("Logging in with OAuth is turned off");
+    }
+
+    let state = pending_logins.start();
+    let url = match Url::parse_with_params(
+        &oauth.authorize_url,
+        &[
+            ("response_type", "code"),
+            ("client_id", oauth.client_id.as_deref().unwrap_or_default()),
+            ("redirect_uri", &oauth.redirect_url),
+            ("scope", &oauth.scopes.join(" ")),
+            ("state", &state),
+        ],
+    ) {
+        Ok(url) => url,
+        Err(e) => {
+            log::error!("oauth.authorize_url isn't a valid URL: {}", e);
+            return redirect_error(oauth, "Logging in isn't set up properly");
+        }
+    };
+
+    // Only this browser gets to finish the login
+    let cookie = Cookie::build(STATE_COOKIE, state)
+        .path("/oauth")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .secure(oauth.redirect_url.starts_with("https://"))
+        .max_age(time::Duration::seconds(LOGIN_TIMEOUT.as_secs() as i64))
+        .finish();
+
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, url.as_str()))
+        .cookie(cookie)
+        .finish()
+}
+
+/// Where the provider sends the player back to. Whatever happens, they're sent
+/// on to the frontend, with either a token or what went wrong.
+#[get("/oauth/callback")]
+pub async fn oauth_callback_route(
+    req: HttpRequest,
+    query: Query<CallbackQuery>,
+    config: Data<Config>,
+    pending_logins: Data<PendingLogins>,
+    db: Data<DatabaseConnection>,
+) -> HttpResponse {
+    let oauth = &config.oauth;
+    if !oauth.enabled {
+        return HttpResponse::NotFound().body("Logging in with OAuth is turned off");
+    }
+
+    // The state has to have been handed out here, to this browser
+    let started_here = match (&query.state, req.cookie(STATE_COOKIE)) {
+        (Some(state), Some(cookie)) if cookie.value() == state => pending_logins.finish(state),
+        _ => false,
+    };
+
+    let hacker = if !started_here {
+        Err(anyhow!("That login expired, try again"))
+    } else if let Some(error) = &query.error {
+        Err(anyhow!("{} said: {}", oauth.provider_name, error))
+    } else if let Some(code) = &query.code {
+        log_in(oauth, code, db.get_ref()).await
+    } else {
+        Err(anyhow!("{} didn't send a code", oauth.provider_name))
+    };
+
+    let fragment = match hacker {
+        Ok(hacker) => {
+            let token = new_session_token(hacker.id, &config.sessions, db.get_ref()).await;
+            format!("token={}", token.token)
+        }
+        // The player only needs the gist of it, the log gets the rest
+        Err(e) => {
+            log::warn!("OAuth login failed: {:#}", e);
+            format!("error={}", encode_fragment(&e.to_string()))
+        }
+    };
+
+    // The login is over either way, so the browser can forget its state
+    let mut cookie = Cookie::build(STATE_COOKIE, "").path("/oauth").finish();
+    cookie.make_removal();
+
+    HttpResponse::Found()
+        .insert_header((
+            header::LOCATION,
+            format!("{}#{}", oauth.frontend_url, fragment),
+        ))
+        .cookie(cookie)
+        .finish()
+}
+
+/// Trade the code for the player's id and name, and find the hacker that goes
+/// with them
+async fn log_in(
+    oauth: &OAuthConfig,
+    code: &str,
+    db: &DatabaseConnection,
+) -> anyhow::Result<hacker::Model> {
+    let client = reqwest::Client::new();
+
+    let token: TokenResponse = client
+        .post(&oauth.token_url)
+        .header(header::ACCEPT, "application/json")
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &oauth.redirect_url),
+            ("client_id", oauth.client_id.as_deref().unwrap_or_default()),
+            (
+                "client_secret",
+                oauth.client_secret.as_deref().unwrap_or_default(),
+            ),
+        ])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("{} didn't accept the code", oauth.provider_name))?
+        .json()
+        .await
+        .context("The access token couldn't be read")?;
+
+    let user: serde_json::Value = client
+        .get(&oauth.userinfo_url)
+        .bearer_auth(token.access_token)
+        .header(header::ACCEPT, "application/json")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("{} didn't say who you are", oauth.provider_name))?
+        .json()
+        .await
+        .context("The user info couldn't be read")?;
+
+    // Ids are numbers with some providers and strings with others
+    let subject = match &user[&oauth.id_field] {
+        serde_json::Value::String(id) => id.clone(),
+        serde_json::Value::Number(id) => id.to_string(),
+        _ => bail!("The user info has no {}", oauth.id_field),
+    };
+    let username = user[&oauth.username_field]
+        .as_str()
+        .unwrap_or(&subject)
+        .to_string();
+
+    find_hacker(oauth, subject, username, db).await
+}
+
+/// The hacker linked to this id with the provider. The first time someone
+/// logs in, they're linked to the hacker the Discord bot made for them if
+/// there is one, or a new hacker otherwise.
+async fn find_hacker(
+    oauth: &OAuthConfig,
+    subject: String,
+    username: String,
+    db: &DatabaseConnection,
+) -> anyhow::Result<hacker::Model> {
+    if let Some(hacker) = linked_hacker(oauth, &subject, db).await? {
+        return Ok(hacker);
+    }
+
+    let discord_id = if oauth.discord_ids {
+        Some(
+            subject
+                .parse::<i64>()
+                .with_context(|| format!("{} isn't a Discord id", subject))?,
+        )
+    } else {
+        None
+    };
+
+    let existing = match discord_id {
+        Some(discord_id) => hacker::Entity::find()
+            .filter(hacker::Column::DiscordId.eq(discord_id))
+            .one(db)
+            .await
+            .context("Failed to get hacker")?,
+        None => None,
+    };
+
+    let (hacker, created) = match existing {
+        Some(hacker) => (hacker, false),
+        // Someone else might already have the provider's name for them
+        None => {
+            let hacker = insert_hacker_with_free_username(
+                db,
+                username,
+                hacker::ActiveModel {
+                    discord_id: Set(discord_id),
+                    ..Default::default()
+                },
+            )
+            .await
+            .context("Failed to create hacker")?;
+            (hacker, true)
+        }
+    };
+
+    let linked = oauth_identity::Entity::insert(oauth_identity::ActiveModel {
+        provider: Set(oauth.provider_name.clone()),
+        subject: Set(subject.clone()),
+        fk_hacker_id: Set(hacker.id),
+    })
+    .on_conflict(
+        OnConflict::columns([
+            oauth_identity::Column::Provider,
+            oauth_identity::Column::Subject,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .exec(db)
+    .await;
+
+    match linked {
+        Ok(_) => Ok(hacker),
+        // Another login for the same player got there first, so use the
+        // hacker it linked instead
+        Err(DbErr::RecordNotInserted) => {
+            if created {
+                hacker
+                    .delete(db)
+                    .await
+                    .context("Failed to remove duplicate hacker")?;
+            }
+
+            linked_hacker(oauth, &subject, db)
+                .await?
+                .context("The OAuth identity disappeared")
+        }
+        Err(e) => Err(e).context("Failed to link OAuth identity"),
+    }
+}
+
+/// The hacker already linked to this id with the provider, if there is one
+async fn linked_hacker(
+    oauth: &OAuthConfig,
+    subject: &str,
+    db: &DatabaseConnection,
+) -> anyhow::Result<Option<hacker::Model>> {
+    let identity = (oauth.provider_name.clone(), subject.to_string());
+    let linked = oauth_identity::Entity::find_by_id(identity)
+        .find_also_related(hacker::Entity)
+        .one(db)
+        .await
+        .context("Failed to get OAuth identity")?;
+
+    Ok(linked.and_then(|(_, hacker)| hacker))
+}
+
+/// Send the player back to the frontend with what went wrong
+fn redirect_error(oauth: &OAuthConfig, error: &str) -> HttpResponse {
+    HttpResponse::Found()
+        .insert_header((
+            header::LOCATION,
+            format!("{}#error={}", oauth.frontend_url, encode_fragment(error)),
+        ))
+        .finish()
+}
+
+/// Percent-encode anything that isn't safe after the `#` of a URL
+fn encode_fragment(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, post, test, web::Form, App, HttpServer};
+    use entity::entities::{team, token};
+    use sea_orm::{
+        ConnectOptions, ConnectionTrait, Database, DbBackend, EntityTrait, PaginatorTrait, Schema,
+    };
+
+    use super::*;
+
+    /// The provider the tests log in with. The code it's given is the
+    /// player's id and name, which it hands back as the access token and then
+    /// as the user info.
+    async fn start_provider() -> String {
+        #[derive(Deserialize)]
+        struct TokenForm {
+            code: String,
+        }
+
+        #[post("/token")]
+        async fn token_route(form: Form<TokenForm>) -> HttpResponse {
+            HttpResponse::Ok().json(serde_json::json!({ "access_token": form.code }))
+        }
+
+        #[get("/me")]
+        async fn userinfo_route(req: HttpRequest) -> HttpResponse {
+            let access_token = req
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                .unwrap_or_default();
+            let (id, username) = access_token.split_once(':').unwrap();
+            HttpResponse::Ok().json(serde_json::json!({ "id": id, "username": username }))
+        }
+
+        let server = HttpServer::new(|| App::new().service(token_route).service(userinfo_route))
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let url = format!("http://{}", server.addrs()[0]);
+        actix_rt::spawn(server.run());
+
+        url
+    }
+
+    async fn config(discord_ids: bool) -> Config {
+        let provider = start_provider().await;
+
+        Config {
+            oauth: OAuthConfig {
+                enabled: true,
+                provider_name: "Stub".to_string(),
+                authorize_url: format!("{}/authorize", provider),
+                token_url: format!("{}/token", provider),
+                userinfo_url: format!("{}/me", provider),
+                frontend_url: "http://frontend".to_string(),
+                discord_ids,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    async fn database() -> DatabaseConnection {
+        // Every connection to an in-memory database gets its own database
+        let mut options = ConnectOptions::new("sqlite::memory:".to_string());
+        options.max_connections(1);
+        let db = Database::connect(options).await.unwrap();
+
+        let schema = Schema::new(DbBackend::Sqlite);
+        for table in [
+            schema.create_table_from_entity(team::Entity),
+            schema.create_table_from_entity(hacker::Entity),
+            schema.create_table_from_entity(oauth_identity::Entity),
+            schema.create_table_from_entity(token::Entity),
+        ] {
+            db.execute(db.get_database_backend().build(&table))
+                .await
+                .unwrap();
+        }
+
+        db
+    }
+
+    /// Go to `/oauth/login`, and come back with the state it made and the
+    /// cookie that goes with it
+    async fn start_login<S>(app: &S) -> (String, Cookie<'static>)
+    where
+        S: actix_web::dev::Service<
+            actix_http::Request,
+            Response = actix_web::dev::ServiceResponse,
+            Error = actix_web::Error,
+        >,
+    {
+        let response = test::call_service(
+            app,
+            test::TestRequest::get().uri("/oauth/login").to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::FOUND);
+
+        let location = Url::parse(location(&response)).unwrap();
+        let state = location
+            .query_pairs()
+            .find(|(name, _)| name == "state")
+            .unwrap()
+            .1
+            .into_owned();
+        let cookie = response
+            .response()
+            .cookies()
+            .find(|cookie| cookie.name() == STATE_COOKIE)
+            .unwrap()
+            .into_owned();
+        assert_eq!(cookie.value(), state);
+
+        (state, cookie)
+    }
+
+    /// Come back from the provider, and return where the player is sent
+    async fn finish_login<S>(app: &S, code: &str, state: &str, cookie: Cookie<'static>) -> String
+    where
+        S: actix_web::dev::Service<
+            actix_http::Request,
+            Response = actix_web::dev::ServiceResponse,
+            Error = actix_web::Error,
+        >,
+    {
+        let uri = format!("/oauth/callback?code={}&state={}", code, state);
+        let request = test::TestRequest::get()
+            .uri(&uri)
+            .cookie(cookie)
+            .to_request();
+        let response = test::call_service(app, request).await;
+        assert_eq!(response.status(), StatusCode::FOUND);
+
+        location(&response).to_string()
+    }
+
+    fn location(response: &actix_web::dev::ServiceResponse) -> &str {
+        response
+            .headers()
+            .get(header::LOCATION)
+            .unwrap()
+            .to_str()
+            .unwrap()
+    }
+
+    /// The hacker the session token the player was sent back with belongs to
+    async fn logged_in_as(location: &str, db: &DatabaseConnection) -> hacker::Model {
+        let token = location
+            .strip_prefix("http://frontend#token=")
+            .unwrap_or_else(|| panic!("Login failed: {}", location));
+        token::Entity::find_by_id(token.to_string())
+            .find_also_related(hacker::Entity)
+            .one(db)
+            .await
+            .unwrap()
+            .and_then(|(_, hacker)| hacker)
+            .unwrap()
+    }
+
+    macro_rules! app {
+        ($config:expr, $db:expr) => {
+            test::init_service(
+                App::new()
+                    .app_data(Data::new($config))
+                    .app_data(Data::new(PendingLogins::default()))
+                    .app_data(Data::new($db.clone()))
+                    .service(oauth_login_route)
+                    .service(oauth_callback_route),
+            )
+            .await
+        };
+    }
+
+    #[actix_web::test]
+    async fn first_login_creates_hacker_and_second_finds_it() {
+        let db = database().await;
+        let app = app!(config(false).await, db);
+
+        let (state, cookie) = start_login(&app).await;
+        let location = finish_login(&app, "42:alice", &state, cookie).await;
+        let first = logged_in_as(&location, &db).await;
+        assert_eq!(first.username, "alice");
+        assert_eq!(first.discord_id, None);
+
+        // The provider's name has changed since, but it's the same player
+        let (state, cookie) = start_login(&app).await;
+        let location = finish_login(&app, "42:alice2", &state, cookie).await;
+        let second = logged_in_as(&location, &db).await;
+        assert_eq!(second, first);
+        assert_eq!(hacker::Entity::find().count(&db).await.unwrap(), 1);
+    }
+
+    #[actix_web::test]
+    async fn discord_ids_link_to_existing_hacker() {
+        let db = database().await;
+        let app = app!(config(true).await, db);
+
+        // Made by the Discord bot before they ever logged in this way
+        let existing = insert_hacker_with_free_username(
+            &db,
+            "bob".to_string(),
+            hacker::ActiveModel {
+                discord_id: Set(Some(1234)),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let (state, cookie) = start_login(&app).await;
+        let location = finish_login(&app, "1234:bobby", &state, cookie).await;
+        assert_eq!(logged_in_as(&location, &db).await, existing);
+
+        let identity = oauth_identity::Entity::find_by_id(("Stub".to_string(), "1234".to_string()))
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(identity.fk_hacker_id, existing.id);
+    }
+
+    #[actix_web::test]
+    async fn reused_or_unknown_state_is_rejected() {
+        let db = database().await;
+        let app = app!(config(false).await, db);
+        let expired = "http://frontend#error=That%20login%20expired%2C%20try%20again";
+
+        let (state, cookie) = start_login(&app).await;
+        finish_login(&app, "42:alice", &state, cookie.clone()).await;
+        assert_eq!(
+            finish_login(&app, "42:alice", &state, cookie).await,
+            expired
+        );
+
+        let unknown = Cookie::new(STATE_COOKIE, "unknown");
+        assert_eq!(
+            finish_login(&app, "42:alice", "unknown", unknown).await,
+            expired
+        );
+
+        // Someone else's login can't be finished in this browser
+        let (state, _) = start_login(&app).await;
+        let (_, other_cookie) = start_login(&app).await;
+        assert_eq!(
+            finish_login(&app, "42:alice", &state, other_cookie).await,
+            expired
+        );
+
+        // Nor can one that never says which login it is
+        let request = test::TestRequest::get()
+            .uri("/oauth/callback?error=access_denied")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(location(&response), expired);
+
+        assert_eq!(hacker::Entity::find().count(&db).await.unwrap(), 1);
+    }
+}
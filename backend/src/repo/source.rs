@@ -0,0 +1,192 @@
+//! Where the challenges come from. Every source leaves the challenges in the
+//! repo folder, ready to be parsed, so the backend can run against a remote
+//! repository during the CTF and a local folder of fixtures during development.
+
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use config::{ChallengeSourceKind, RepoConfig};
+use flate2::read::GzDecoder;
+use git2::Repository;
+use tar::Archive;
+
+/// How many times a remote repository is cloned when reloading, before the
+/// reload gives up
+pub const RELOAD_CLONE_ATTEMPTS: u32 = 3;
+
+pub trait ChallengeSource {
+    /// Put the challenges into `path`
+    fn fetch(&self, path: &Path) -> anyhow::Result<()>;
+
+    /// Whether the challenges are parsed where they already are, instead of
+    /// being fetched into a new folder
+    fn in_place(&self) -> bool {
+        false
+    }
+}
+
+/// The source the config asks for. A remote repository is cloned up to
+/// `attempts` times, or until it works if that's `None`.
+pub fn from_config(repo_config: &RepoConfig, attempts: Option<u32>) -> Box<dyn ChallengeSource> {
+    match repo_config.source {
+        ChallengeSourceKind::Git => Box::new(RemoteGit {
+            url: repo_config.url.clone(),
+            retry: Duration::from_secs(repo_config.clone_retry_secs),
+            attempts,
+        }),
+        ChallengeSourceKind::LocalGit => Box::new(LocalGit {
+            path: PathBuf::from(&repo_config.url),
+        }),
+        ChallengeSourceKind::Directory => Box::new(Directory),
+        ChallengeSourceKind::Tarball => Box::new(Tarball {
+            // Checked when the config is loaded
+            archive: repo_config.archive.clone().unwrap_or_default(),
+        }),
+    }
+}
+
+/// A git repository on a server somewhere. Cloning is tried again if it
+/// fails, since the network might not be up yet.
+pub struct RemoteGit {
+    url: String,
+    retry: Duration,
+    attempts: Option<u32>,
+}
+
+impl ChallengeSource for RemoteGit {
+    fn fetch(&self, path: &Path) -> anyhow::Result<()> {
+        let mut attempt = 1;
+        loop {
+            // A failed clone can leave half a repository behind
+            clear(path)?;
+
+            match Repository::clone(&self.url, path) {
+                Ok(_) => return Ok(()),
+                Err(e) if self.attempts != Some(attempt) => {
+                    println!("failed to clone: {}", e);
+                    std::thread::sleep(self.retry);
+                    attempt += 1;
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| {
+                        format!("Failed to clone {} after {} attempt(s)", self.url, attempt)
+                    })
+                }
+            };
+        }
+    }
+}
+
+/// A git repository that's already on this machine, bare or not
+pub struct LocalGit {
+    path: PathBuf,
+}
+
+impl ChallengeSource for LocalGit {
+    fn fetch(&self, path: &Path) -> anyhow::Result<()> {
+        if !self.path.exists() {
+            bail!("There's no git repository at {}", self.path.display());
+        }
+
+        clear(path)?;
+
+        let url = self
+            .path
+            .to_str()
+            .with_context(|| format!("{} isn't a valid path", self.path.display()))?;
+        Repository::clone(url, path)
+            .with_context(|| format!("Failed to clone {}", self.path.display()))?;
+
+        Ok(())
+    }
+}
+
+/// A folder that already has the challenges in it. Nothing is copied or
+/// deleted, so challenges can be edited in place.
+pub struct Directory;
+
+impl ChallengeSource for Directory {
+    fn fetch(&self, path: &Path) -> anyhow::Result<()> {
+        if !path.is_dir() {
+            bail!("There's no challenge folder at {}", path.display());
+        }
+
+        Ok(())
+    }
+
+    fn in_place(&self) -> bool {
+        true
+    }
+}
+
+/// A `.tar.gz` bundle with the category folders at the top level, such as
+/// one made with `tar -czf challenges.tar.gz -C ctf .`
+pub struct Tarball {
+    archive: PathBuf,
+}
+
+impl ChallengeSource for Tarball {
+    fn fetch(&self, path: &Path) -> anyhow::Result<()> {
+        let file = File::open(&self.archive)
+            .with_context(|| format!("Failed to open {}", self.archive.display()))?;
+
+        clear(path)?;
+
+        Archive::new(GzDecoder::new(file))
+            .unpack(path)
+            .with_context(|| format!("Failed to unpack {}", self.archive.display()))?;
+
+        Ok(())
+    }
+}
+
+/// Make sure that the folder doesn't exist, so it can be filled from scratch
+fn clear(path: &Path) -> anyhow::Result<()> {
+    if path.exists() {
+        fs::remove_dir_all(path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Put the freshly fetched challenges in `new` where the old ones in `path`
+/// were. The old folder is only removed once the new one is in place.
+pub fn replace(new: &Path, path: &Path) -> anyhow::Result<()> {
+    let old = sibling(path, "old");
+    clear(&old)?;
+
+    if path.exists() {
+        fs::rename(path, &old)
+            .with_context(|| format!("Failed to move {} out of the way", path.display()))?;
+    }
+
+    if let Err(e) = fs::rename(new, path) {
+        // Put the old challenges back, so there's still something there
+        let _ = fs::rename(&old, path);
+        return Err(e).with_context(|| format!("Failed to move {} into place", new.display()));
+    }
+
+    clear(&old)
+}
+
+/// A folder next to `path`, so it's on the same filesystem and can be renamed
+/// to it
+pub fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "ctf".to_string());
+
+    path.with_file_name(format!(".{}-{}", name, suffix))
+}
+
+/// Remove a folder that's no longer needed, if it's there
+pub fn discard(path: &Path) {
+    if let Err(e) = clear(path) {
+        println!("{:#}", e);
+    }
+}
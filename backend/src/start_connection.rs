@@ -6,14 +6,24 @@ use actix_web::{
     Error, HttpRequest, HttpResponse,
 };
 use actix_web_actors::ws;
+use common::encoding::Encoding;
+use config::Config;
 
 #[get("/ws")]
 pub async fn start_connection_route(
     req: HttpRequest,
     stream: Payload,
     srv: Data<Addr<CTFServer>>,
+    config: Data<Config>,
 ) -> Result<HttpResponse, Error> {
-    let ws = WsConn::new(srv.get_ref().clone());
+    // The client picks how messages are encoded with the query string, such
+    // as /ws?encoding=cbor
+    let encoding = match Encoding::from_query(req.query_string()) {
+        Ok(encoding) => encoding,
+        Err(e) => return Ok(HttpResponse::BadRequest().body(e)),
+    };
+
+    let ws = WsConn::new(srv.get_ref().clone(), &config, encoding);
 
     ws::start(ws, &req, stream)
 }
@@ -8,25 +8,46 @@ use actix::{
 };
 use actix_web_actors::ws::{self, Message};
 
-use common::{ClientId, NetworkMessage};
+use common::{
+    ctf_message::{ClientMessage, ClientUpdate, ServerError, ServerMessage},
+    encoding::{Encoding, Frame},
+    ClientId, PROTOCOL_VERSION,
+};
+use config::Config;
 use std::time::{Duration, Instant};
 use uuid::Uuid;
 
-const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
-const CLIENT_TIMEOUT: Duration = Duration::from_secs(3);
-
 pub struct WsConn {
     ctf_server_addr: Addr<CTFServer>,
     hb: Instant,
     id: ClientId,
+    heartbeat_interval: Duration,
+    client_timeout: Duration,
+    /// How messages to this client are encoded, picked when it connected
+    encoding: Encoding,
+    /// Whether the client has said hello with a protocol version we speak
+    handshake_done: bool,
+    /// Sent along with the welcome
+    login_options: ClientUpdate,
 }
 
 impl WsConn {
-    pub fn new(game_server: Addr<CTFServer>) -> WsConn {
+    pub fn new(game_server: Addr<CTFServer>, config: &Config, encoding: Encoding) -> WsConn {
         WsConn {
             id: Uuid::new_v4(),
             hb: Instant::now(),
             ctf_server_addr: game_server,
+            heartbeat_interval: Duration::from_secs(config.heartbeat.interval_secs),
+            client_timeout: Duration::from_secs(config.heartbeat.client_timeout_secs),
+            encoding,
+            handshake_done: false,
+            login_options: ClientUpdate::LoginOptions {
+                password_accounts: config.accounts.enabled,
+                oauth_provider: config
+                    .oauth
+                    .enabled
+                    .then(|| config.oauth.provider_name.clone()),
+            },
         }
     }
 }
@@ -62,20 +83,16 @@ impl Actor for WsConn {
 
 impl WsConn {
     fn hb(&self, ctx: &mut ws::WebsocketContext<Self>) {
-        ctx.run_interval(HEARTBEAT_INTERVAL, |act, ctx| {
-            if Instant::now().duration_since(act.hb) > CLIENT_TIMEOUT {
+        ctx.run_interval(self.heartbeat_interval, |act, ctx| {
+            if Instant::now().duration_since(act.hb) > act.client_timeout {
                 println!("Disconnecting failed heartbeat");
                 act.ctf_server_addr.do_send(Disconnect { id: act.id });
                 ctx.stop();
                 return;
             }
 
-            // Encode a heartbeat and send it to the client
-            ctx.ping(
-                serde_json::to_string(&NetworkMessage::Heartbeat)
-                    .unwrap()
-                    .as_bytes(),
-            );
+            // Ping the client, it should pong back
+            ctx.ping(b"");
         });
 
         // // Once a second, send the elapsed time that this client has been
@@ -93,20 +110,13 @@ impl StreamHandler<Result<Message, ws::ProtocolError>> for WsConn {
             Ok(msg) => {
                 match msg {
                     // Any message we get from the client should be encoded as
-                    // a NetworkMessage. It will then get passed on to either
-                    // the game server, or a game room.
+                    // a ClientMessage, as JSON text or CBOR binary. It will
+                    // then get passed on to the game server.
                     Message::Text(text) => {
-                        // Deserialize as a NetworkMessage
-                        let message: NetworkMessage = serde_json::from_str(&text).unwrap();
-
-                        if let NetworkMessage::CTFMessage(message) = message {
-                            // Send the message to the CTFServer actor to be
-                            // handled
-                            self.ctf_server_addr.do_send(IncomingCTFRequest {
-                                ctf_message: message,
-                                id: self.id,
-                            })
-                        }
+                        self.handle_frame(Frame::Text(text.to_string()), ctx);
+                    }
+                    Message::Binary(bytes) => {
+                        self.handle_frame(Frame::Binary(bytes.to_vec()), ctx);
                     }
                     // If we get a pong back, update the heartbeat
                     Message::Pong(_) => {
@@ -120,20 +130,69 @@ impl StreamHandler<Result<Message, ws::ProtocolError>> for WsConn {
                 ctx.stop();
             }
         }
+    }
+}
+
+impl WsConn {
+    fn handle_frame(&mut self, frame: Frame, ctx: &mut ws::WebsocketContext<Self>) {
+        // Deserialize as a ClientMessage. A client sending something else is
+        // broken, but that's no reason to take the connection down.
+        let message: ClientMessage = match frame.decode() {
+            Ok(message) => message,
+            Err(e) => {
+                println!("Error: {}", e);
+                self.send_error(ServerError::MalformedMessage(e.to_string()), ctx);
+                return;
+            }
+        };
+
+        match message {
+            // The client tells us which protocol it speaks before anything
+            // else. If it's not ours, there's nothing useful we can say to
+            // each other, so hang up.
+            ClientMessage::Hello { protocol_version } => {
+                if protocol_version == PROTOCOL_VERSION {
+                    self.handshake_done = true;
+                    send_client_message(
+                        ServerMessage::Welcome {
+                            protocol_version: PROTOCOL_VERSION,
+                        },
+                        self.encoding,
+                        ctx,
+                    );
+                    send_client_message(
+                        ServerMessage::ClientUpdate(self.login_options.clone()),
+                        self.encoding,
+                        ctx,
+                    );
+                } else {
+                    self.send_error(
+                        ServerError::IncompatibleVersion {
+                            server: PROTOCOL_VERSION,
+                            client: protocol_version,
+                        },
+                        ctx,
+                    );
+                    ctx.close(Some(ws::CloseReason {
+                        code: ws::CloseCode::Policy,
+                        description: Some("Incompatible protocol version".to_string()),
+                    }));
+                    ctx.stop();
+                }
+            }
+            _ if !self.handshake_done => {
+                self.send_error(ServerError::HandshakeRequired, ctx);
+            }
+            // Send the message to the CTFServer actor to be handled
+            message => self.ctf_server_addr.do_send(IncomingCTFRequest {
+                ctf_message: message,
+                id: self.id,
+            }),
+        }
+    }
 
-        // if let Ok(Message::Binary(bytes)) = msg {
-        //     match from_reader::<ClientAction, _>(Cursor::new(bytes)) {
-        //         Ok(message) => {
-        //             // Send the message to be handled by the room
-        //             // self.game_server_addr.do_send(ActorMessage::NetworkMessage(
-        //             //     NetworkMessage::ClientAction(_message),
-        //             // ));
-        //         }
-        //         Err(e) => {
-        //             dbg!(e);
-        //         }
-        //     }
-        // }
+    fn send_error(&self, error: ServerError, ctx: &mut ws::WebsocketContext<Self>) {
+        send_client_message(ServerMessage::Error(error), self.encoding, ctx);
     }
 }
 
@@ -144,16 +203,23 @@ impl Handler<WsActorMessage> for WsConn {
         match msg {
             // Pass all network messages right to the game server
             WsActorMessage::IncomingMessage(network_message) => {
-                send_client_message(network_message, ctx);
+                send_client_message(network_message, self.encoding, ctx);
             }
             WsActorMessage::ActorRequest(actor_request) => match actor_request {},
             WsActorMessage::OutgoingMessage(network_message) => {
-                send_client_message(network_message, ctx);
+                send_client_message(network_message, self.encoding, ctx);
             }
         }
     }
 }
 
-fn send_client_message(network_message: NetworkMessage, ctx: &mut ws::WebsocketContext<WsConn>) {
-    ctx.text(serde_json::to_string(&network_message).unwrap());
+fn send_client_message(
+    network_message: ServerMessage,
+    encoding: Encoding,
+    ctx: &mut ws::WebsocketContext<WsConn>,
+) {
+    match encoding.encode(&network_message) {
+        Frame::Text(text) => ctx.text(text),
+        Frame::Binary(bytes) => ctx.binary(bytes),
+    }
 }
@@ -0,0 +1,141 @@
+//! Running `ctf-lint` against a small repo for each kind of problem it finds.
+//! The repos are in `fixtures/lint`, next to the clean one in `fixtures/repo`.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde_json::Value;
+
+const FLAG_FORMAT: &str = r"h4tt\{.+\}";
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/// Run `ctf-lint` on a fixture, and get back its exit code and output. It's
+/// run from the fixtures folder with none of the config's environment
+/// variables, so only the arguments decide what it checks.
+fn lint(args: &[&str]) -> (i32, String, String) {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_ctf-lint"));
+    command.args(args).current_dir(fixtures());
+    for (name, _) in env::vars() {
+        if name.starts_with("CTF_") {
+            command.env_remove(name);
+        }
+    }
+
+    let output = command.output().unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+/// The problems `ctf-lint` found in a fixture, which has to have some
+fn problems(fixture: &str) -> Vec<String> {
+    let (code, stdout, stderr) = lint(&[fixture, "--flag-format", FLAG_FORMAT]);
+    assert_eq!(code, 1, "{}{}", stdout, stderr);
+
+    let mut lines: Vec<String> = stdout.lines().map(str::to_string).collect();
+    let summary = lines.pop().unwrap();
+    assert!(summary.starts_with("Checked "), "{}", summary);
+
+    lines
+}
+
+#[test]
+fn clean_repo_passes() {
+    let (code, stdout, _) = lint(&["repo", "--flag-format", FLAG_FORMAT]);
+    assert_eq!(code, 0);
+    assert_eq!(
+        stdout,
+        "Checked 3 challenge(s) in repo, found 0 problem(s)\n"
+    );
+}
+
+#[test]
+fn duplicate_titles() {
+    let (code, stdout, _) = lint(&["lint/duplicate-titles"]);
+    assert_eq!(code, 1);
+    assert_eq!(
+        stdout,
+        "web/second: The title \"Same\" is already used by web/first\n\
+         Checked 1 challenge(s) in lint/duplicate-titles, found 1 problem(s)\n"
+    );
+}
+
+#[test]
+fn bad_flags() {
+    let problems = problems("lint/bad-flags");
+    assert_eq!(problems[0], "web/empty: A flag is empty");
+    assert_eq!(
+        problems[1],
+        "web/format: Flag \"flag{format}\" doesn't match the flag format"
+    );
+    assert_eq!(problems[2], "web/none: There aren't any flags");
+    assert!(
+        problems[3].starts_with("web/regex: Invalid flag: \"h4tt\\\\{(\" isn't a valid regex"),
+        "{}",
+        problems[3]
+    );
+}
+
+#[test]
+fn unknown_requirements() {
+    assert_eq!(
+        problems("lint/unknown-requirements"),
+        [
+            "web/locked: Requires \"Missing\", which doesn't exist",
+            "web/locked: Requires points in pwn, which doesn't exist",
+        ]
+    );
+}
+
+#[test]
+fn requirement_cycle() {
+    assert_eq!(
+        problems("lint/requirement-cycle"),
+        ["web/chicken: Challenges require each other in a loop: Chicken -> Egg -> Chicken"]
+    );
+}
+
+#[test]
+fn missing_files() {
+    assert_eq!(
+        problems("lint/missing-files"),
+        [
+            "web/download: References files/missing.txt, which doesn't exist",
+            "web/no-json: challenge.json is missing",
+        ]
+    );
+}
+
+#[test]
+fn json_report() {
+    let (code, stdout, _) = lint(&["lint/duplicate-titles", "--json"]);
+    assert_eq!(code, 1);
+
+    let report: Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(report["challenges"], 1);
+    assert_eq!(report["problems"][0]["folder"], "web/second");
+    assert_eq!(
+        report["problems"][0]["message"],
+        "The title \"Same\" is already used by web/first"
+    );
+}
+
+#[test]
+fn bad_flag_format_is_a_usage_error() {
+    let (code, stdout, stderr) = lint(&["repo", "--flag-format", "("]);
+    assert_eq!(code, 2);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr.starts_with("\"(\" isn't a valid flag format"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,347 @@
+//! Fetching the challenges from each kind of source, parsing them, and
+//! bringing the database in line with them.
+//!
+//! `fixtures/repo` is a small challenge repo, and `fixtures/repo.tar.gz` is the
+//! same repo bundled for the tarball source. Rebuild the bundle whenever the
+//! folder changes:
+//!
+//! tar -czf fixtures/repo.tar.gz -C fixtures/repo .
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+use backend::repo::{source, Repo};
+use config::{ChallengeSourceKind, RepoConfig};
+use entity::entities::{
+    blob, challenge, challenge_file, challenge_flag, challenge_requirement, hint,
+};
+use git2::{IndexAddOption, Repository, Signature};
+use sea_orm::{
+    ColumnTrait, ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DbBackend,
+    EntityTrait, PaginatorTrait, QueryFilter, Schema,
+};
+
+fn fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/repo")
+}
+
+fn fixture_archive() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/repo.tar.gz")
+}
+
+/// An empty folder that only one test uses, removed once the test is done
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new(name: &str) -> Self {
+        let path = env::temp_dir().join(format!("ctf-repo-test-{}-{}", process::id(), name));
+        if path.exists() {
+            fs::remove_dir_all(&path).unwrap();
+        }
+        fs::create_dir_all(&path).unwrap();
+
+        Self(path)
+    }
+
+    fn join(&self, path: &str) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn copy_folder(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let path = entry.unwrap().path();
+        let target = to.join(path.file_name().unwrap());
+        if path.is_dir() {
+            copy_folder(&path, &target);
+        } else {
+            fs::copy(&path, &target).unwrap();
+        }
+    }
+}
+
+/// Change some text in one of the challenge files of a copied repo
+fn edit(path: &Path, from: &str, to: &str) {
+    let text = fs::read_to_string(path).unwrap();
+    assert!(
+        text.contains(from),
+        "{} doesn't have {:?}",
+        path.display(),
+        from
+    );
+    fs::write(path, text.replace(from, to)).unwrap();
+}
+
+fn repo_config(source: ChallengeSourceKind, path: &Path) -> RepoConfig {
+    RepoConfig {
+        source,
+        path: path.to_path_buf(),
+        flag_format: Some(r"h4tt\{.+\}".to_string()),
+        ..Default::default()
+    }
+}
+
+fn parse(path: &Path) -> Repo {
+    Repo::parse_repo(&repo_config(ChallengeSourceKind::Directory, path)).unwrap()
+}
+
+async fn database() -> DatabaseConnection {
+    // Every connection to an in-memory database gets its own database
+    let mut options = ConnectOptions::new("sqlite::memory:".to_string());
+    options.max_connections(1);
+    let db = Database::connect(options).await.unwrap();
+
+    let schema = Schema::new(DbBackend::Sqlite);
+    for table in [
+        schema.create_table_from_entity(blob::Entity),
+        schema.create_table_from_entity(challenge::Entity),
+        schema.create_table_from_entity(challenge_flag::Entity),
+        schema.create_table_from_entity(challenge_requirement::Entity),
+        schema.create_table_from_entity(hint::Entity),
+        schema.create_table_from_entity(challenge_file::Entity),
+    ] {
+        db.execute(db.get_database_backend().build(&table))
+            .await
+            .unwrap();
+    }
+
+    db
+}
+
+async fn find_challenge(title: &str, db: &DatabaseConnection) -> challenge::Model {
+    challenge::Entity::find()
+        .filter(challenge::Column::Title.eq(title))
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap_or_else(|| panic!("{} isn't in the database", title))
+}
+
+#[test]
+fn directory_is_parsed_where_it_is() {
+    let config = repo_config(ChallengeSourceKind::Directory, &fixture());
+    let source = source::from_config(&config, None);
+    assert!(source.in_place());
+    source.fetch(&config.path).unwrap();
+
+    // The template isn't a category
+    let repo = Repo::parse_repo(&config).unwrap();
+    assert_eq!(repo.len(), 3);
+
+    let scratch = Scratch::new("directory");
+    let missing = scratch.join("missing");
+    assert!(source.fetch(&missing).is_err());
+}
+
+#[test]
+fn tarball_replaces_whatever_was_there() {
+    let scratch = Scratch::new("tarball");
+    let path = scratch.join("ctf");
+    fs::create_dir_all(path.join("stale/challenge")).unwrap();
+
+    let config = RepoConfig {
+        archive: Some(fixture_archive()),
+        ..repo_config(ChallengeSourceKind::Tarball, &path)
+    };
+    Repo::fetch_repo(&config).unwrap();
+
+    assert!(!path.join("stale").exists());
+    assert!(path.join("web/intro/files/notes/todo.txt").is_file());
+    assert_eq!(Repo::parse_repo(&config).unwrap().len(), 3);
+}
+
+#[test]
+fn local_git_is_cloned() {
+    let scratch = Scratch::new("local-git");
+
+    // Commit the fixture to a repository of its own
+    let origin = scratch.join("origin");
+    copy_folder(&fixture(), &origin);
+    let git = Repository::init(&origin).unwrap();
+    let mut index = git.index().unwrap();
+    index.add_all(["*"], IndexAddOption::DEFAULT, None).unwrap();
+    index.write().unwrap();
+    let tree = git.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("fixtures", "fixtures@example.com").unwrap();
+    git.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "Challenges",
+        &tree,
+        &[],
+    )
+    .unwrap();
+
+    let path = scratch.join("ctf");
+    let config = RepoConfig {
+        url: origin.to_string_lossy().into_owned(),
+        ..repo_config(ChallengeSourceKind::LocalGit, &path)
+    };
+    Repo::fetch_repo(&config).unwrap();
+
+    assert!(path.join(".git").is_dir());
+    assert_eq!(Repo::parse_repo(&config).unwrap().len(), 3);
+}
+
+#[test]
+fn failed_refetch_keeps_the_old_challenges() {
+    let scratch = Scratch::new("refetch");
+    let path = scratch.join("ctf");
+    let mut config = RepoConfig {
+        archive: Some(fixture_archive()),
+        ..repo_config(ChallengeSourceKind::Tarball, &path)
+    };
+    Repo::fetch_repo(&config).unwrap();
+
+    config.archive = Some(path.with_file_name("missing.tar.gz"));
+    assert!(Repo::refetch_repo(&config).is_err());
+
+    assert_eq!(Repo::parse_repo(&config).unwrap().len(), 3);
+    assert!(!source::sibling(&path, "new").exists());
+}
+
+#[test]
+fn broken_challenges_are_all_reported() {
+    let scratch = Scratch::new("broken");
+    let path = scratch.join("ctf");
+    copy_folder(&fixture(), &path);
+    edit(
+        &path.join("web/intro/challenge.json"),
+        "h4tt{intro}",
+        "intro",
+    );
+    edit(
+        &path.join("crypto/rsa/challenge.json"),
+        "\"minimum\": 100",
+        "\"minimum\": 900",
+    );
+
+    let config = repo_config(ChallengeSourceKind::Directory, &path);
+    let error = format!("{:#}", Repo::parse_repo(&config).err().unwrap());
+    assert!(error.starts_with("Found 2 problem(s)"), "{}", error);
+    assert!(error.contains("web/intro: Flag \"intro\" doesn't match the flag format"));
+    assert!(error.contains("crypto/rsa: The minimum points (900)"));
+}
+
+#[actix_rt::test]
+async fn apply_adds_everything_then_nothing() {
+    let db = database().await;
+    let repo = parse(&fixture());
+
+    let changes = repo.update_database(&db).await.unwrap();
+    assert_eq!(changes.added, ["RSA", "Intro", "Next"]);
+    assert!(changes.updated.is_empty());
+
+    assert_eq!(challenge::Entity::find().count(&db).await.unwrap(), 3);
+    assert_eq!(challenge_flag::Entity::find().count(&db).await.unwrap(), 4);
+    assert_eq!(hint::Entity::find().count(&db).await.unwrap(), 1);
+    assert_eq!(challenge_file::Entity::find().count(&db).await.unwrap(), 2);
+    assert_eq!(blob::Entity::find().count(&db).await.unwrap(), 2);
+
+    let rsa = find_challenge("RSA", &db).await;
+    assert_eq!(rsa.category, "crypto");
+    assert_eq!(rsa.repo_path.as_deref(), Some("crypto/rsa"));
+    assert_eq!((rsa.minimum_points, rsa.decay), (Some(100), Some(10)));
+
+    let intro = find_challenge("Intro", &db).await;
+    let next = find_challenge("Next", &db).await;
+    let requirement = challenge_requirement::Entity::find()
+        .filter(challenge_requirement::Column::FkChallengeId.eq(next.id))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(requirement.fk_required_challenge_id, Some(intro.id));
+
+    // Loading the same challenges again doesn't touch anything
+    let changes = parse(&fixture()).update_database(&db).await.unwrap();
+    assert!(changes.is_empty(), "{:?}", changes);
+}
+
+#[actix_rt::test]
+async fn renamed_challenge_keeps_its_row() {
+    let db = database().await;
+    parse(&fixture()).update_database(&db).await.unwrap();
+    let intro = find_challenge("Intro", &db).await;
+
+    let scratch = Scratch::new("rename");
+    let path = scratch.join("ctf");
+    copy_folder(&fixture(), &path);
+    edit(
+        &path.join("web/intro/challenge.json"),
+        "\"Intro\"",
+        "\"Web 101\"",
+    );
+    edit(
+        &path.join("web/next/challenge.json"),
+        "\"Intro\"",
+        "\"Web 101\"",
+    );
+
+    let changes = parse(&path).update_database(&db).await.unwrap();
+    assert_eq!(
+        changes.renamed,
+        [("Intro".to_string(), "Web 101".to_string())]
+    );
+    assert!(changes.added.is_empty() && changes.retired.is_empty());
+
+    // Next still requires the same row
+    assert_eq!(find_challenge("Web 101", &db).await.id, intro.id);
+    assert!(changes.updated.is_empty(), "{:?}", changes.updated);
+}
+
+#[actix_rt::test]
+async fn moved_challenge_is_matched_by_title() {
+    let db = database().await;
+    parse(&fixture()).update_database(&db).await.unwrap();
+    let rsa = find_challenge("RSA", &db).await;
+
+    let scratch = Scratch::new("move");
+    let path = scratch.join("ctf");
+    copy_folder(&fixture(), &path);
+    fs::rename(path.join("crypto"), path.join("math")).unwrap();
+
+    let changes = parse(&path).update_database(&db).await.unwrap();
+    assert_eq!(changes.updated, ["RSA"]);
+    assert!(changes.added.is_empty() && changes.retired.is_empty());
+
+    let moved = find_challenge("RSA", &db).await;
+    assert_eq!(moved.id, rsa.id);
+    assert_eq!(moved.category, "math");
+    assert_eq!(moved.repo_path.as_deref(), Some("math/rsa"));
+}
+
+#[actix_rt::test]
+async fn removed_challenge_is_retired_until_it_comes_back() {
+    let db = database().await;
+    parse(&fixture()).update_database(&db).await.unwrap();
+
+    let scratch = Scratch::new("retire");
+    let path = scratch.join("ctf");
+    copy_folder(&fixture(), &path);
+    fs::remove_dir_all(path.join("crypto/rsa")).unwrap();
+
+    let changes = parse(&path).update_database(&db).await.unwrap();
+    assert_eq!(changes.retired, ["RSA"]);
+    assert!(find_challenge("RSA", &db).await.retired);
+    assert_eq!(challenge::Entity::find().count(&db).await.unwrap(), 3);
+
+    // It's only retired once
+    let changes = parse(&path).update_database(&db).await.unwrap();
+    assert!(changes.is_empty(), "{:?}", changes);
+
+    let changes = parse(&fixture()).update_database(&db).await.unwrap();
+    assert_eq!(changes.updated, ["RSA"]);
+    assert!(!find_challenge("RSA", &db).await.retired);
+}
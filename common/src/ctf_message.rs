@@ -1,45 +1,122 @@
 use std::collections::HashMap;
 
 use entity::{
-    entities::{challenge, hacker, submission, team},
-    helpers::get_team_solved_challenges,
+    entities::{
+        challenge, challenge_file, cheat_suspicion, hacker, hint, hint_unlock, submission, team,
+    },
+    helpers::{
+        get_challenge_solve_counts, get_event, get_first_bloods, get_team_solved_challenges,
+    },
 };
 use iter_tools::Itertools;
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
 
-pub type DiscordClientId = i64;
+use crate::{
+    scoring::challenge_points,
+    unlocks::{describe_requirement, get_challenge_requirements, get_unlocked_challenges},
+    SessionId,
+};
+
+pub use entity::entities::sea_orm_active_enums::{CheatReason, Role};
+
+/// A hacker's own id. It has nothing to do with their Discord id, since not
+/// every hacker has one.
+pub type HackerId = i64;
 
+/// Anything a client sends to the server
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum CTFMessage {
+pub enum ClientMessage {
+    /// The first thing a client sends, so the server can check that they
+    /// speak the same protocol. This has to stay the first variant, so any
+    /// version of the server can read it.
+    Hello {
+        protocol_version: u32,
+    },
     /// A client wants to connect and get information about the game, but isn't
     /// authenticated
     Connect,
     /// Login token being submitted
     Login(String),
+    /// Someone making an account that logs in with a password instead of
+    /// Discord. They need an invite code from the organizers.
+    Register {
+        username: String,
+        password: String,
+        invite_code: String,
+    },
+    /// Username and password being submitted, for an account made with
+    /// `Register`
+    PasswordLogin {
+        username: String,
+        password: String,
+    },
+    /// A client lost its connection and wants its session back, along with
+    /// anything it missed
+    Resume(SessionId),
     /// A client wants to be logged out
     Logout,
-    /// A subset of the information stored in the CTF state, to be passed to the client
-    CTFClientStateComponent(CTFClientStateComponent),
+    /// A client wants every session logged in as them to be logged out, and
+    /// every token they were given to stop working
+    LogoutEverywhere,
+    /// An organizer wants every session of the hacker with this name logged
+    /// out, and their tokens to stop working
+    RevokeHacker(String),
     SubmitFlag {
-        challenge_name: String,
+        challenge_id: ChallengeId,
         flag: String,
     },
-    /// Tell a specific client that something that matters to them has happened
-    /// (They submitted a flag correctly a team member went offline, etc.)
-    ClientUpdate(ClientUpdate),
     /// Team token being submitted by player
     JoinTeam(String),
     /// Team name being submitted by player
     CreateTeam(String),
     /// Player leaving their team
     LeaveTeam,
+    /// The team captain wants the hacker with this name off the team
+    KickMember(String),
+    /// The team captain wants the hacker with this name to be captain instead
+    TransferCaptain(String),
+    /// The team captain wants a new join token, so the old one stops working
+    RegenerateJoinToken,
+    /// Spend some of the team's points on a hint for a challenge. The hint is
+    /// its position in the challenge's list of hints.
+    UnlockHint {
+        challenge_id: ChallengeId,
+        hint: usize,
+    },
+    /// Asking for a token to download one of a challenge's files with
+    RequestDownload {
+        challenge_id: ChallengeId,
+        name: String,
+    },
+    /// Organizers asking for every suspected case of flag sharing
+    RequestCheatSuspicions,
+    /// Organizers asking for the challenges to be loaded from the repo again
+    ReloadChallenges,
+    /// A client missed an update to the GlobalData, so it wants all of it
+    /// again
+    RequestGlobalData,
     /// Debug messages
     ResetDB,
     SpawnTeams,
     CloneRepo,
 }
 
+/// Anything the server sends to a client
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ServerMessage {
+    /// The server understood the client's hello. This has to stay the first
+    /// variant, so any version of the client can read it.
+    Welcome { protocol_version: u32 },
+    /// A subset of the information stored in the CTF state, to be passed to the client
+    CTFClientStateComponent(CTFClientStateComponent),
+    /// Tell a specific client that something that matters to them has happened
+    /// (They submitted a flag correctly a team member went offline, etc.)
+    ClientUpdate(ClientUpdate),
+    /// The server couldn't do anything with the last message the client sent
+    Error(ServerError),
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct CTFState {
     pub hacker_teams: Vec<HackerTeam>,
@@ -71,13 +148,10 @@ impl CTFState {
     // }
 
     /// Build a hackers's team data
-    pub async fn get_hacker_team_data(
-        client_id: DiscordClientId,
-        db: &DatabaseConnection,
-    ) -> TeamData {
+    pub async fn get_hacker_team_data(client_id: HackerId, db: &DatabaseConnection) -> TeamData {
         // Get the hacker
         let hacker = hacker::Entity::find()
-            .filter(hacker::Column::DiscordId.eq(client_id))
+            .filter(hacker::Column::Id.eq(client_id))
             .one(db)
             .await
             .expect("Failed to get hacker")
@@ -103,26 +177,35 @@ impl CTFState {
             .await
             .expect("Failed to get hackers");
 
-        TeamData::OnTeam(HackerTeam {
-            name: team.name,
-            join_token: team.join_token,
-            hackers: hackers
-                .iter()
-                .map(|h| Hacker {
-                    name: h.username.clone(),
-                })
-                .collect(),
-        })
+        let captain = hackers
+            .iter()
+            .find(|h| Some(h.id) == team.fk_captain_id)
+            .map(|h| h.username.clone());
+
+        TeamData::OnTeam {
+            team: HackerTeam {
+                name: team.name,
+                join_token: team.join_token,
+                captain,
+                hackers: hackers
+                    .iter()
+                    .map(|h| Hacker {
+                        name: h.username.clone(),
+                    })
+                    .collect(),
+            },
+            is_captain: team.fk_captain_id == Some(client_id),
+        }
     }
 
     // Build a hacker's client data
     pub async fn get_hacker_client_data(
-        client_id: DiscordClientId,
+        client_id: HackerId,
         db: &DatabaseConnection,
     ) -> ClientData {
         // Get the hacker
         let hacker = hacker::Entity::find()
-            .filter(hacker::Column::DiscordId.eq(client_id))
+            .filter(hacker::Column::Id.eq(client_id))
             .one(db)
             .await
             .expect("Failed to get hacker")
@@ -130,35 +213,196 @@ impl CTFState {
 
         ClientData::LoggedIn {
             username: hacker.username,
+            role: hacker.role,
         }
     }
 
     // Build the game data
-    pub async fn get_game_data(db: &DatabaseConnection) -> GameData {
+    /// The challenges as a team sees them. Challenges the team hasn't unlocked
+    /// yet are still listed, but without anything that would help solve them.
+    pub async fn get_game_data(team_id: Option<TeamId>, db: &DatabaseConnection) -> GameData {
         // Get all the challenges
         let challenges = challenge::Entity::find()
             .all(db)
             .await
             .expect("Failed to get challenges");
 
-        // TODO: Only send them challenges that their team has unlocked if there
-        // are pre-requisites
+        // First bloods made after the scoreboard freeze stay hidden, the same
+        // as the solves themselves
+        let freeze = get_event(db).await.and_then(|event| event.freeze);
+        let first_bloods = get_first_bloods(db).await;
+
+        let unlocked = get_unlocked_challenges(team_id, db).await;
+
+        // Dynamic challenges are worth less the more teams have solved them,
+        // but solves after the freeze would give away that they happened
+        let solve_counts = get_challenge_solve_counts(db, freeze).await;
+
+        // Hints are listed with their cost, but only show their text once the
+        // team has paid for them
+        let hints = hint::Entity::find()
+            .all(db)
+            .await
+            .expect("Failed to get hints");
+        let files = challenge_file::Entity::find()
+            .all(db)
+            .await
+            .expect("Failed to get challenge files");
+
+        let unlocked_hints: Vec<i32> = match team_id {
+            Some(team_id) => hint_unlock::Entity::find()
+                .filter(hint_unlock::Column::FkTeamId.eq(team_id))
+                .all(db)
+                .await
+                .expect("Failed to get hint unlocks")
+                .into_iter()
+                .map(|unlock| unlock.fk_hint_id)
+                .collect(),
+            None => Vec::new(),
+        };
+        let requirements = get_challenge_requirements(db).await;
+        let titles: HashMap<i32, String> = challenges
+            .iter()
+            .map(|challenge| (challenge.id, challenge.title.clone()))
+            .collect();
+
         GameData::LoggedIn {
             challenges: challenges
                 .iter()
-                .filter(|challenge| challenge.active)
-                .map(|challenge| CTFChallenge {
-                    title: challenge.title.clone(),
-                    category: challenge.category.clone(),
-                    description: challenge.description.clone(),
-                    link: challenge.link.clone(),
-                    points: challenge.points,
-                    author: challenge.author.clone(),
+                .filter(|challenge| challenge.active && !challenge.retired)
+                .map(|challenge| {
+                    let locked = !unlocked.contains(&challenge.id);
+
+                    CTFChallenge {
+                        id: challenge.id,
+                        title: challenge.title.clone(),
+                        category: challenge.category.clone(),
+                        description: if locked {
+                            String::new()
+                        } else {
+                            challenge.description.clone()
+                        },
+                        link: if locked {
+                            String::new()
+                        } else {
+                            challenge.link.clone()
+                        },
+                        points: challenge_points(
+                            challenge,
+                            solve_counts.get(&challenge.id).copied().unwrap_or(0),
+                        ),
+                        author: challenge.author.clone(),
+                        first_blood: first_bloods
+                            .get(&challenge.id)
+                            .filter(|(solve, _)| {
+                                !matches!(freeze, Some(freeze) if solve.time >= freeze)
+                            })
+                            .map(|(_, team)| team.name.clone()),
+                        locked,
+                        requires: requirements
+                            .get(&challenge.id)
+                            .map(|requirements| {
+                                requirements
+                                    .iter()
+                                    .map(|requirement| describe_requirement(requirement, &titles))
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                        hints: if locked {
+                            Vec::new()
+                        } else {
+                            hints
+                                .iter()
+                                .filter(|hint| hint.fk_challenge_id == challenge.id)
+                                .sorted_by_key(|hint| hint.position)
+                                .map(|hint| CTFHint {
+                                    cost: hint.cost,
+                                    text: unlocked_hints
+                                        .contains(&hint.id)
+                                        .then(|| hint.text.clone()),
+                                })
+                                .collect()
+                        },
+                        files: if locked {
+                            Vec::new()
+                        } else {
+                            files
+                                .iter()
+                                .filter(|file| file.fk_challenge_id == challenge.id)
+                                .sorted_by(|a, b| a.name.cmp(&b.name))
+                                .map(|file| CTFFile {
+                                    name: file.name.clone(),
+                                    size: file.size as u64,
+                                    sha256: file.fk_blob_hash.clone(),
+                                })
+                                .collect()
+                        },
+                    }
                 })
                 .collect(),
         }
     }
 
+    /// Every suspected case of flag sharing, newest first
+    pub async fn get_cheat_suspicions(db: &DatabaseConnection) -> Vec<CheatSuspicion> {
+        let suspicions = cheat_suspicion::Entity::find()
+            .all(db)
+            .await
+            .expect("Failed to get cheat suspicions");
+
+        // Look up the names of everything the suspicions point at
+        let challenges: HashMap<i32, String> = challenge::Entity::find()
+            .all(db)
+            .await
+            .expect("Failed to get challenges")
+            .into_iter()
+            .map(|challenge| (challenge.id, challenge.title))
+            .collect();
+        let teams: HashMap<i32, String> = team::Entity::find()
+            .all(db)
+            .await
+            .expect("Failed to get teams")
+            .into_iter()
+            .map(|team| (team.id, team.name))
+            .collect();
+        let hackers: HashMap<HackerId, String> = hacker::Entity::find()
+            .all(db)
+            .await
+            .expect("Failed to get hackers")
+            .into_iter()
+            .map(|hacker| (hacker.id, hacker.username))
+            .collect();
+
+        suspicions
+            .into_iter()
+            .sorted_by(|a, b| b.time.cmp(&a.time))
+            .map(|suspicion| CheatSuspicion {
+                time_millis: suspicion.time.timestamp_millis() as u128,
+                reason: suspicion.reason,
+                challenge: challenges
+                    .get(&suspicion.fk_challenge_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                team: teams
+                    .get(&suspicion.fk_team_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                hacker: hackers
+                    .get(&suspicion.fk_hacker_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                source_team: teams
+                    .get(&suspicion.fk_source_team_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                source_hacker: suspicion
+                    .fk_source_hacker_id
+                    .and_then(|hacker_id| hackers.get(&hacker_id).cloned()),
+                flag: suspicion.flag,
+            })
+            .collect()
+    }
+
     /// Rebuild the state from the database. It might be better to just update
     /// state then flush it to the database or something, but whatever, it's a
     /// cheap operation on this size of data.
@@ -189,9 +433,12 @@ impl CTFState {
                     })
                     .collect::<Vec<Hacker>>();
 
+                // Anyone could join a team with its token, so only its own
+                // members get to see it
                 HackerTeam {
                     name: team.name.clone(),
-                    join_token: team.join_token.clone(),
+                    join_token: String::new(),
+                    captain: None,
                     hackers,
                 }
             })
@@ -206,12 +453,24 @@ impl CTFState {
             .map(|team| (team.name.clone(), team.id))
             .collect::<HashMap<TeamName, TeamId>>();
 
-        // Find all the correct submissions
-        let _solves = submission::Entity::find()
-            .filter(submission::Column::Correct.eq(true))
+        // Get the event schedule. Solves after the scoreboard freeze are kept
+        // off the public scoreboard.
+        let event = get_event(db).await;
+        let freeze = event.as_ref().and_then(|event| event.freeze);
+
+        // Every solve of a challenge is worth its current value, so count how
+        // many teams have solved each one. Solves after the freeze don't
+        // lower it, or the scoreboard would show that they happened.
+        let solve_counts = get_challenge_solve_counts(db, freeze).await;
+
+        let hint_unlocks: Vec<(hint_unlock::Model, hint::Model)> = hint_unlock::Entity::find()
+            .find_also_related(hint::Entity)
             .all(db)
             .await
-            .expect("Failed to get all submissions");
+            .expect("Failed to get hint unlocks")
+            .into_iter()
+            .filter_map(|(unlock, hint)| Some((unlock, hint?)))
+            .collect();
 
         let mut scoreboard: Scoreboard = Scoreboard {
             teams: HashMap::new(),
@@ -222,6 +481,10 @@ impl CTFState {
                 get_team_solved_challenges(db, *team_names.get(&team.name).unwrap()).await;
 
             for (solve, challenge) in challenges {
+                if matches!(freeze, Some(freeze) if solve.time >= freeze) {
+                    continue;
+                }
+
                 // TODO: Check that we're not giving multiple points for the
                 // same challenge
                 scoreboard
@@ -229,8 +492,36 @@ impl CTFState {
                     .entry(team.name.clone())
                     .or_insert(Vec::new())
                     .push(Solve {
-                        points: challenge.points as u32,
+                        points: challenge_points(
+                            &challenge,
+                            solve_counts.get(&challenge.id).copied().unwrap_or(0),
+                        ) + solve.bonus_points,
                         time_millis: solve.time.timestamp_millis() as u128,
+                        blood: solve.blood.map(|blood| blood as u8),
+                        challenge_id: Some(challenge.id),
+                        bonus_points: solve.bonus_points,
+                    });
+            }
+
+            // Hints cost points when they're unlocked
+            for (unlock, hint) in hint_unlocks
+                .iter()
+                .filter(|(unlock, _)| unlock.fk_team_id == team_names[&team.name])
+            {
+                if matches!(freeze, Some(freeze) if unlock.time >= freeze) {
+                    continue;
+                }
+
+                scoreboard
+                    .teams
+                    .entry(team.name.clone())
+                    .or_default()
+                    .push(Solve {
+                        points: -hint.cost,
+                        time_millis: unlock.time.timestamp_millis() as u128,
+                        blood: None,
+                        challenge_id: None,
+                        bonus_points: 0,
                     });
             }
         }
@@ -244,11 +535,18 @@ impl CTFState {
             })
             .collect::<Vec<Hacker>>();
 
-        // Return the new state
+        // Return the new state. The server gives it a sequence number when
+        // it's sent out.
         GlobalData {
+            seq: 0,
             hacker_teams: teams,
             non_hacker_teams,
             scoreboard,
+            event: event.map(|event| EventSchedule {
+                start_millis: event.start.timestamp_millis() as u128,
+                end_millis: event.end.timestamp_millis() as u128,
+                freeze_millis: event.freeze.map(|freeze| freeze.timestamp_millis() as u128),
+            }),
         }
     }
 }
@@ -286,32 +584,180 @@ impl Default for CTFClientState {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum CTFClientStateComponent {
     GlobalData(GlobalData),
+    /// A change to the GlobalData. Each one is numbered one after the last,
+    /// so a client can tell if it missed one.
+    GlobalDataUpdate {
+        seq: u64,
+        update: GlobalDataUpdate,
+    },
     GameData(GameData),
     TeamData(TeamData),
     ClientData(ClientData),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct GlobalData {
+    /// The number of the last update this includes
+    pub seq: u64,
     pub hacker_teams: Vec<HackerTeam>,
     pub non_hacker_teams: Vec<Hacker>,
     pub scoreboard: Scoreboard,
+    pub event: Option<EventSchedule>,
+}
+
+/// Something that changed in the GlobalData. These are sent instead of the
+/// whole thing, which gets big with a lot of teams.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum GlobalDataUpdate {
+    /// A new team with nobody on it yet
+    TeamAdded { name: TeamName },
+    /// A hacker logged in, and might not be listed yet
+    HackerAdded {
+        hacker: Hacker,
+        team: Option<TeamName>,
+    },
+    /// A hacker joined or left a team
+    HackerMoved {
+        hacker: String,
+        from: Option<TeamName>,
+        to: Option<TeamName>,
+    },
+    /// Something changed a team's score
+    SolveAdded { team: TeamName, solve: Solve },
+    /// A challenge's solves are worth this much now, before any bonus, since
+    /// another team solved it
+    ChallengeRescored {
+        challenge_id: ChallengeId,
+        points: i32,
+    },
+}
+
+impl GlobalData {
+    /// Make a change to this copy of the data. Returns whether anything
+    /// actually changed.
+    pub fn apply(&mut self, update: &GlobalDataUpdate) -> bool {
+        match update {
+            GlobalDataUpdate::TeamAdded { name } => {
+                if self.hacker_teams.iter().any(|team| &team.name == name) {
+                    return false;
+                }
+
+                // Teams are kept sorted by name
+                let index = self.hacker_teams.partition_point(|team| team.name < *name);
+                self.hacker_teams.insert(
+                    index,
+                    HackerTeam {
+                        name: name.clone(),
+                        join_token: String::new(),
+                        captain: None,
+                        hackers: Vec::new(),
+                    },
+                );
+                true
+            }
+            GlobalDataUpdate::HackerAdded { hacker, team } => {
+                let hackers = match self.hackers_mut(team.as_ref()) {
+                    Some(hackers) => hackers,
+                    None => return false,
+                };
+                if hackers.iter().any(|listed| listed.name == hacker.name) {
+                    return false;
+                }
+
+                hackers.push(hacker.clone());
+                true
+            }
+            GlobalDataUpdate::HackerMoved { hacker, from, to } => {
+                if from == to {
+                    return false;
+                }
+
+                if let Some(hackers) = self.hackers_mut(from.as_ref()) {
+                    if let Some(index) = hackers.iter().position(|listed| &listed.name == hacker) {
+                        hackers.remove(index);
+                    }
+                }
+                if let Some(hackers) = self.hackers_mut(to.as_ref()) {
+                    hackers.push(Hacker {
+                        name: hacker.clone(),
+                    });
+                }
+                true
+            }
+            GlobalDataUpdate::SolveAdded { team, solve } => {
+                self.scoreboard
+                    .teams
+                    .entry(team.clone())
+                    .or_default()
+                    .push(solve.clone());
+                true
+            }
+            GlobalDataUpdate::ChallengeRescored {
+                challenge_id,
+                points,
+            } => {
+                let mut changed = false;
+                for solve in self.scoreboard.teams.values_mut().flatten() {
+                    if solve.challenge_id == Some(*challenge_id)
+                        && solve.points != points + solve.bonus_points
+                    {
+                        solve.points = points + solve.bonus_points;
+                        changed = true;
+                    }
+                }
+                changed
+            }
+        }
+    }
+
+    /// The hackers on a team, or the ones on no team
+    fn hackers_mut(&mut self, team: Option<&TeamName>) -> Option<&mut Vec<Hacker>> {
+        match team {
+            Some(name) => self
+                .hacker_teams
+                .iter_mut()
+                .find(|team| &team.name == name)
+                .map(|team| &mut team.hackers),
+            None => Some(&mut self.non_hacker_teams),
+        }
+    }
+}
+
+/// When the CTF starts and ends, in milliseconds since the epoch
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EventSchedule {
+    pub start_millis: u128,
+    pub end_millis: u128,
+    /// After this, the scoreboard stops showing new solves
+    pub freeze_millis: Option<u128>,
 }
 
 pub type TeamId = i32;
 pub type TeamName = String;
+/// Stays the same when a challenge is renamed, so it's what messages use to
+/// point at a challenge
+pub type ChallengeId = i32;
 
 /// For the scoreboard, we're going to need to know what solves the team has
 /// made, and at what times.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Scoreboard {
     pub teams: HashMap<TeamName, Vec<Solve>>,
 }
 
+/// Something that changed a team's score. Usually that's solving a challenge,
+/// but unlocking a hint costs points.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Solve {
-    pub points: u32,
+    /// Includes any first blood bonus, and is negative for hints
+    pub points: i32,
     pub time_millis: u128,
+    /// 1 for first blood, 2 for second and 3 for third
+    pub blood: Option<u8>,
+    /// The challenge that was solved. Hints don't have one.
+    pub challenge_id: Option<ChallengeId>,
+    /// The first blood bonus included in the points
+    pub bonus_points: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -320,22 +766,63 @@ pub enum GameData {
     LoggedIn { challenges: Vec<CTFChallenge> },
 }
 
+impl GameData {
+    /// Other teams' solves change what challenges are worth, but aren't worth
+    /// sending everyone a whole new GameData for, so the challenge list follows
+    /// the rescores in the GlobalData updates
+    pub fn apply(&mut self, update: &GlobalDataUpdate) {
+        if let GlobalDataUpdate::ChallengeRescored {
+            challenge_id,
+            points,
+        } = update
+        {
+            if let Some(challenge) = self.challenge_mut(*challenge_id) {
+                challenge.points = *points;
+            }
+        }
+    }
+
+    /// Show who got first blood on a challenge
+    pub fn set_first_blood(&mut self, challenge_id: ChallengeId, team: TeamName) {
+        if let Some(challenge) = self.challenge_mut(challenge_id) {
+            challenge.first_blood = Some(team);
+        }
+    }
+
+    fn challenge_mut(&mut self, challenge_id: ChallengeId) -> Option<&mut CTFChallenge> {
+        match self {
+            GameData::LoggedOut => None,
+            GameData::LoggedIn { challenges } => challenges
+                .iter_mut()
+                .find(|challenge| challenge.id == challenge_id),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum TeamData {
     NoTeam,
-    OnTeam(HackerTeam),
+    OnTeam {
+        team: HackerTeam,
+        /// Whether this hacker is the team's captain, who can kick members,
+        /// hand over the captaincy and change the join token
+        is_captain: bool,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum ClientData {
     LoggedOut,
-    LoggedIn { username: String },
+    LoggedIn { username: String, role: Role },
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct HackerTeam {
     pub name: String,
+    /// Empty except in the TeamData of the team's own members
     pub join_token: String,
+    /// The captain's name. Also only in the team's own TeamData.
+    pub captain: Option<String>,
     pub hackers: Vec<Hacker>,
 }
 
@@ -346,23 +833,93 @@ pub struct Hacker {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CTFChallenge {
+    pub id: ChallengeId,
+    /// Only for showing to players
     pub title: String,
     pub category: String,
     pub description: String,
     pub link: String,
     pub points: i32,
     pub author: String,
+    /// The team that solved this challenge first
+    pub first_blood: Option<TeamName>,
+    /// Whether the team still has to meet this challenge's requirements. The
+    /// description and link are left out until they do.
+    pub locked: bool,
+    /// What it takes to unlock this challenge
+    pub requires: Vec<String>,
+    /// In the order they should be unlocked
+    pub hints: Vec<CTFHint>,
+    /// Files that can be downloaded from `/files/<challenge id>/<name>`
+    pub files: Vec<CTFFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CTFFile {
+    pub name: String,
+    /// In bytes
+    pub size: u64,
+    /// Hex encoded, so players can check their download
+    pub sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CTFHint {
+    /// How many points the team loses for unlocking it
+    pub cost: i32,
+    /// Only sent once the team has unlocked it
+    pub text: Option<String>,
+}
+
+/// A wrong submission that looks like it came from another team
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CheatSuspicion {
+    pub time_millis: u128,
+    pub reason: CheatReason,
+    pub challenge: String,
+    /// The team and hacker that submitted it
+    pub team: TeamName,
+    pub hacker: String,
+    /// The team it looks like it came from. There's only a hacker if they
+    /// submitted it as well.
+    pub source_team: TeamName,
+    pub source_hacker: Option<String>,
+    pub flag: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum ClientUpdate {
-    /// This client correctly authenticated with a token
+    /// The ways this server lets players log in, besides a token from the
+    /// Discord bot. Sent right after the handshake.
+    LoginOptions {
+        password_accounts: bool,
+        /// The name of the provider `/oauth/login` sends players to
+        oauth_provider: Option<String>,
+    },
+    /// This client correctly authenticated with a token or password
     Authenticated {
-        discord_username: String,
+        username: String,
         valid_token: String,
     }, // <-- TODO: Send them their discord info
     /// This client entered an incorrect token
     IncorrectToken,
+    /// This client entered a username and password that don't match an
+    /// account
+    IncorrectPassword,
+    /// This client couldn't make an account, for this reason
+    RegistrationFailed(String),
+    /// This client's token was right, but it's too old to use
+    TokenExpired,
+    /// This client's session was logged out from somewhere else, for this
+    /// reason
+    LoggedOut(String),
+    /// This client's session can be picked back up with this id if the
+    /// connection drops
+    SessionStarted(SessionId),
+    /// This client got its session back. Anything it missed follows this.
+    Resumed,
+    /// This client's session is gone, so it needs to log in again
+    ResumeFailed,
     /// This client scored a point
     ScoredPoint(String),
     /// This client's team scored a point
@@ -371,4 +928,60 @@ pub enum ClientUpdate {
     IncorrectFlag(String),
     /// General notification
     Notification(String),
+    /// This client tried to do something their role doesn't allow
+    PermissionDenied(String),
+    /// A team was one of the first three to solve a challenge. Sent to
+    /// everyone.
+    FirstBlood {
+        team: TeamName,
+        challenge: String,
+        challenge_id: ChallengeId,
+        /// 1 for first blood, 2 for second and 3 for third
+        blood: u8,
+    },
+    /// Every suspected case of flag sharing. Only sent to organizers.
+    CheatSuspicions(Vec<CheatSuspicion>),
+    /// Solving a challenge unlocked these challenges for the team
+    ChallengesUnlocked(Vec<String>),
+    /// This client is submitting flags too quickly, and has to wait this many
+    /// seconds before submitting another
+    RateLimited { retry_after: u64 },
+    /// The file can be downloaded from `/files/<challenge_id>/<name>` with
+    /// this token for the next minute
+    DownloadReady {
+        challenge_id: ChallengeId,
+        name: String,
+        token: String,
+    },
+}
+
+/// Why the server couldn't handle a message. The connection stays open for
+/// everything but an incompatible version.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ServerError {
+    /// The client and server speak different versions of the protocol, so the
+    /// client needs to be updated
+    IncompatibleVersion { server: u32, client: u32 },
+    /// The client sent something before saying hello
+    HandshakeRequired,
+    /// The message couldn't be read at all
+    MalformedMessage(String),
+    /// The message can't be handled right now, such as logging in when
+    /// already logged in
+    UnexpectedMessage(String),
+}
+
+impl std::fmt::Display for ServerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerError::IncompatibleVersion { server, client } => write!(
+                f,
+                "The server speaks version {} of the protocol, but this client speaks version {}. Try reloading the page.",
+                server, client
+            ),
+            ServerError::HandshakeRequired => write!(f, "The client has to say hello first"),
+            ServerError::MalformedMessage(e) => write!(f, "The server couldn't read that: {}", e),
+            ServerError::UnexpectedMessage(e) => write!(f, "{}", e),
+        }
+    }
 }
@@ -0,0 +1,222 @@
+//! How `ClientMessage`s and `ServerMessage`s are written on the websocket.
+//!
+//! Each connection picks an encoding when it opens, by adding `encoding=cbor`
+//! or `encoding=json` to the websocket URL's query string. Anything that
+//! doesn't ask gets JSON. JSON is sent in text frames and CBOR in binary
+//! frames, so whichever side is reading can tell them apart without having to
+//! remember what was picked.
+
+use std::fmt;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Encoding {
+    #[default]
+    Json,
+    Cbor,
+}
+
+/// A message ready to be sent as a websocket frame
+#[derive(Debug, Clone)]
+pub enum Frame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+/// A frame that didn't hold the message that was expected
+#[derive(Debug)]
+pub struct DecodeError(String);
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Couldn't decode the message: {}", self.0)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl Encoding {
+    /// Get the encoding a connection asked for from the query string of the
+    /// websocket URL, such as `encoding=cbor`
+    pub fn from_query(query: &str) -> Result<Self, String> {
+        match query
+            .split('&')
+            .filter_map(|pair| pair.strip_prefix("encoding="))
+            .next_back()
+        {
+            None | Some("json") => Ok(Encoding::Json),
+            Some("cbor") => Ok(Encoding::Cbor),
+            Some(other) => Err(format!("Unknown encoding {}", other)),
+        }
+    }
+
+    /// What to put in a websocket URL's query string to ask for this encoding
+    pub fn query(&self) -> &'static str {
+        match self {
+            Encoding::Json => "encoding=json",
+            Encoding::Cbor => "encoding=cbor",
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, message: &T) -> Frame {
+        match self {
+            Encoding::Json => Frame::Text(
+                serde_json::to_string(message).expect("Failed to encode a message as JSON"),
+            ),
+            Encoding::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::ser::into_writer(message, &mut bytes)
+                    .expect("Failed to encode a message as CBOR");
+                Frame::Binary(bytes)
+            }
+        }
+    }
+}
+
+impl Frame {
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T, DecodeError> {
+        match self {
+            Frame::Text(text) => serde_json::from_str(text).map_err(|e| DecodeError(e.to_string())),
+            Frame::Binary(bytes) => {
+                ciborium::de::from_reader(bytes.as_slice()).map_err(|e| DecodeError(e.to_string()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::{
+        ctf_message::{
+            CTFClientStateComponent, ClientMessage, ClientUpdate, GlobalDataUpdate, ServerError,
+            ServerMessage,
+        },
+        PROTOCOL_VERSION,
+    };
+
+    fn client_messages() -> Vec<ClientMessage> {
+        vec![
+            ClientMessage::Hello {
+                protocol_version: PROTOCOL_VERSION,
+            },
+            ClientMessage::Connect,
+            ClientMessage::Resume(Uuid::new_v4()),
+            ClientMessage::SubmitFlag {
+                challenge_id: 3,
+                flag: "h4tt{ünïcode}".to_string(),
+            },
+            ClientMessage::UnlockHint {
+                challenge_id: 3,
+                hint: 1,
+            },
+            ClientMessage::ResetDB,
+        ]
+    }
+
+    fn server_messages() -> Vec<ServerMessage> {
+        vec![
+            ServerMessage::Welcome {
+                protocol_version: PROTOCOL_VERSION,
+            },
+            ServerMessage::CTFClientStateComponent(CTFClientStateComponent::GlobalDataUpdate {
+                seq: 42,
+                update: GlobalDataUpdate::ChallengeRescored {
+                    challenge_id: 3,
+                    points: 250,
+                },
+            }),
+            ServerMessage::ClientUpdate(ClientUpdate::DownloadReady {
+                challenge_id: 3,
+                name: "a.txt".to_string(),
+                token: "token".to_string(),
+            }),
+            ServerMessage::ClientUpdate(ClientUpdate::ChallengesUnlocked(vec![])),
+            ServerMessage::Error(ServerError::IncompatibleVersion {
+                server: PROTOCOL_VERSION,
+                client: PROTOCOL_VERSION - 1,
+            }),
+        ]
+    }
+
+    /// The messages don't implement `PartialEq`, so they're compared by how
+    /// they're printed
+    fn assert_round_trips<T: Serialize + DeserializeOwned + fmt::Debug>(
+        encoding: Encoding,
+        messages: &[T],
+    ) {
+        for message in messages {
+            let frame = encoding.encode(message);
+            match (encoding, &frame) {
+                (Encoding::Json, Frame::Text(_)) | (Encoding::Cbor, Frame::Binary(_)) => (),
+                _ => panic!("{:?} was sent in the wrong kind of frame", encoding),
+            }
+
+            let decoded: T = frame.decode().unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", message));
+        }
+    }
+
+    #[test]
+    fn client_messages_round_trip() {
+        assert_round_trips(Encoding::Json, &client_messages());
+        assert_round_trips(Encoding::Cbor, &client_messages());
+    }
+
+    #[test]
+    fn server_messages_round_trip() {
+        assert_round_trips(Encoding::Json, &server_messages());
+        assert_round_trips(Encoding::Cbor, &server_messages());
+    }
+
+    #[test]
+    fn garbage_doesnt_decode() {
+        assert!(Frame::Text("{".to_string())
+            .decode::<ClientMessage>()
+            .is_err());
+        assert!(Frame::Binary(vec![0xff, 0x00])
+            .decode::<ClientMessage>()
+            .is_err());
+    }
+
+    #[test]
+    fn query_defaults_to_json() {
+        assert_eq!(Encoding::from_query(""), Ok(Encoding::Json));
+        assert_eq!(Encoding::from_query("token=abc"), Ok(Encoding::Json));
+        assert_eq!(Encoding::from_query("encoding=json"), Ok(Encoding::Json));
+        assert_eq!(
+            Encoding::from_query("token=abc&encoding=cbor"),
+            Ok(Encoding::Cbor)
+        );
+    }
+
+    #[test]
+    fn query_rejects_unknown_encodings() {
+        assert!(Encoding::from_query("encoding=xml").is_err());
+        assert!(Encoding::from_query("encoding=").is_err());
+        assert!(Encoding::from_query("encoding=CBOR").is_err());
+    }
+
+    #[test]
+    fn query_uses_the_last_encoding() {
+        assert_eq!(
+            Encoding::from_query("encoding=json&encoding=cbor"),
+            Ok(Encoding::Cbor)
+        );
+        assert_eq!(
+            Encoding::from_query("encoding=cbor&encoding=json"),
+            Ok(Encoding::Json)
+        );
+        assert!(Encoding::from_query("encoding=cbor&encoding=xml").is_err());
+    }
+
+    #[test]
+    fn query_matches_from_query() {
+        for encoding in [Encoding::Json, Encoding::Cbor] {
+            assert_eq!(Encoding::from_query(encoding.query()), Ok(encoding));
+        }
+    }
+}
@@ -1,20 +1,20 @@
-use ctf_message::CTFMessage;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 pub mod ctf_message;
+pub mod encoding;
+pub mod scoring;
+pub mod unlocks;
 
 pub type ClientId = Uuid;
 pub type RoomId = Uuid;
+/// Picks a logged in session back up after the websocket reconnects
+pub type SessionId = Uuid;
 
-/// This message represents anything that can be sent over the network
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum NetworkMessage {
-    Heartbeat,
-    Time(u64),
-    UserDisconnected(ClientId),
-    CTFMessage(CTFMessage),
-}
+/// The version of `ClientMessage` and `ServerMessage` this build speaks. It
+/// goes up whenever they change in a way that an older client or server
+/// couldn't read.
+pub const PROTOCOL_VERSION: u32 = 8;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ServerData {
@@ -0,0 +1,81 @@
+use entity::entities::{challenge, sea_orm_active_enums::ScoringMode};
+
+/// How many points a challenge is worth once `solves` teams have solved it.
+///
+/// Dynamic challenges start at `points` and decay quadratically towards
+/// `minimum_points`. The first solve doesn't count towards the decay, so it
+/// reaches the minimum once `decay` more teams have solved it after that, at
+/// `decay + 1` solves. Every team that solved a dynamic challenge is given its
+/// current value, so earlier solves lose points as more teams solve it.
+pub fn challenge_points(challenge: &challenge::Model, solves: u64) -> i32 {
+    match challenge.scoring_mode {
+        ScoringMode::Static => challenge.points,
+        ScoringMode::Dynamic => {
+            let initial = challenge.points;
+            let minimum = challenge.minimum_points.unwrap_or(0).min(initial);
+            let decay = match challenge.decay {
+                Some(decay) if decay > 0 => decay as f64,
+                _ => return initial,
+            };
+
+            // The first solve doesn't lower the value
+            let solves = solves.saturating_sub(1) as f64;
+
+            let value =
+                (minimum - initial) as f64 / (decay * decay) * (solves * solves) + initial as f64;
+
+            (value.ceil() as i32).max(minimum)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dynamic(points: i32, minimum_points: i32, decay: i32) -> challenge::Model {
+        challenge::Model {
+            id: 1,
+            title: "Test".to_string(),
+            category: "misc".to_string(),
+            description: String::new(),
+            link: String::new(),
+            points,
+            author: String::new(),
+            active: true,
+            scoring_mode: ScoringMode::Dynamic,
+            minimum_points: Some(minimum_points),
+            decay: Some(decay),
+            repo_path: None,
+            retired: false,
+        }
+    }
+
+    #[test]
+    fn first_solve_keeps_full_value() {
+        let challenge = dynamic(500, 100, 10);
+
+        assert_eq!(challenge_points(&challenge, 0), 500);
+        assert_eq!(challenge_points(&challenge, 1), 500);
+        assert!(challenge_points(&challenge, 2) < 500);
+    }
+
+    #[test]
+    fn reaches_minimum_at_decay_plus_one_solves() {
+        let challenge = dynamic(500, 100, 10);
+
+        assert!(challenge_points(&challenge, 10) > 100);
+        assert_eq!(challenge_points(&challenge, 11), 100);
+        assert_eq!(challenge_points(&challenge, 50), 100);
+    }
+
+    #[test]
+    fn static_and_undecaying_challenges_never_change() {
+        let mut challenge = dynamic(500, 100, 0);
+        assert_eq!(challenge_points(&challenge, 20), 500);
+
+        challenge.scoring_mode = ScoringMode::Static;
+        challenge.decay = Some(10);
+        assert_eq!(challenge_points(&challenge, 20), 500);
+    }
+}
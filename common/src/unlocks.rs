@@ -0,0 +1,129 @@
+//! Working out which challenges a team has unlocked.
+//!
+//! A challenge with requirements stays locked until a team meets all of them.
+//! A requirement is either solving another challenge, or scoring some number
+//! of points in a category. Category points are counted at each challenge's
+//! full value, so dynamic scoring can't lock a challenge again after a team
+//! has unlocked it.
+
+use std::collections::{HashMap, HashSet};
+
+use entity::{
+    entities::{challenge, challenge_requirement},
+    helpers::get_team_solved_challenges,
+};
+use sea_orm::{DatabaseConnection, EntityTrait};
+
+use crate::ctf_message::TeamId;
+
+/// Get the ids of every challenge this team has unlocked. Hackers that aren't
+/// on a team have only unlocked the challenges without any requirements.
+pub async fn get_unlocked_challenges(
+    team_id: Option<TeamId>,
+    db: &DatabaseConnection,
+) -> HashSet<i32> {
+    let challenges = challenge::Entity::find()
+        .all(db)
+        .await
+        .expect("Failed to get challenges");
+    let requirements = get_challenge_requirements(db).await;
+
+    let solved = match team_id {
+        Some(team_id) => get_team_solved_challenges(db, team_id)
+            .await
+            .into_iter()
+            .map(|(_, challenge)| challenge)
+            .collect(),
+        None => Vec::new(),
+    };
+
+    unlocked_challenges(&challenges, &requirements, &solved)
+}
+
+/// Get the requirements of every challenge, by the id of the challenge they
+/// lock
+pub async fn get_challenge_requirements(
+    db: &DatabaseConnection,
+) -> HashMap<i32, Vec<challenge_requirement::Model>> {
+    challenge_requirement::Entity::find()
+        .all(db)
+        .await
+        .expect("Failed to get challenge requirements")
+        .into_iter()
+        .fold(HashMap::new(), |mut requirements, requirement| {
+            requirements
+                .entry(requirement.fk_challenge_id)
+                .or_insert_with(Vec::new)
+                .push(requirement);
+            requirements
+        })
+}
+
+/// The ids of the challenges a team has unlocked, given the challenges they've
+/// already solved
+pub fn unlocked_challenges(
+    challenges: &[challenge::Model],
+    requirements: &HashMap<i32, Vec<challenge_requirement::Model>>,
+    solved: &[challenge::Model],
+) -> HashSet<i32> {
+    let solved_ids: HashSet<i32> = solved.iter().map(|challenge| challenge.id).collect();
+
+    let mut category_points: HashMap<&str, i32> = HashMap::new();
+    for challenge in solved {
+        *category_points.entry(&challenge.category).or_insert(0) += challenge.points;
+    }
+
+    challenges
+        .iter()
+        .filter(|challenge| {
+            requirements
+                .get(&challenge.id)
+                .map(|requirements| {
+                    requirements
+                        .iter()
+                        .all(|requirement| is_met(requirement, &solved_ids, &category_points))
+                })
+                .unwrap_or(true)
+        })
+        .map(|challenge| challenge.id)
+        .collect()
+}
+
+fn is_met(
+    requirement: &challenge_requirement::Model,
+    solved_ids: &HashSet<i32>,
+    category_points: &HashMap<&str, i32>,
+) -> bool {
+    match (
+        requirement.fk_required_challenge_id,
+        &requirement.category,
+        requirement.points,
+    ) {
+        (Some(required), _, _) => solved_ids.contains(&required),
+        (None, Some(category), Some(points)) => {
+            category_points.get(category.as_str()).copied().unwrap_or(0) >= points
+        }
+        // A requirement that doesn't ask for anything
+        _ => true,
+    }
+}
+
+/// Describe a requirement for the players, such as "Solve Intro" or "300
+/// points in web"
+pub fn describe_requirement(
+    requirement: &challenge_requirement::Model,
+    titles: &HashMap<i32, String>,
+) -> String {
+    match (
+        requirement.fk_required_challenge_id,
+        &requirement.category,
+        requirement.points,
+    ) {
+        (Some(required), _, _) => format!(
+            "Solve {}",
+            titles.get(&required).map(String::as_str).unwrap_or("?")
+        ),
+        (None, Some(category), Some(points)) => format!("{} points in {}", points, category),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,94 @@
+use std::{sync::Arc, time::Duration};
+
+use config::Config;
+use entity::entities::{challenge, event, submission, team};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+use serenity::{http::Http, model::id::ChannelId};
+
+/// Keep checking the database for first bloods that haven't been announced
+/// yet, and post them in the announcement channel
+pub async fn announce_first_bloods(http: Arc<Http>, db: DatabaseConnection, config: Config) {
+    // Nothing to do if there's nowhere to announce them
+    let Some(channel_id) = config.discord.announcement_channel_id else {
+        return;
+    };
+    let channel = ChannelId(channel_id);
+
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(config.discord.announcement_poll_secs));
+
+    loop {
+        interval.tick().await;
+
+        // A database hiccup shouldn't stop the announcements for the rest of
+        // the CTF, so just try again next time
+        if let Err(why) = announce_new_bloods(&http, &db, channel).await {
+            println!("Failed to check for first bloods: {}", why);
+        }
+    }
+}
+
+/// Post every first blood that hasn't been announced yet
+async fn announce_new_bloods(
+    http: &Http,
+    db: &DatabaseConnection,
+    channel: ChannelId,
+) -> Result<(), DbErr> {
+    let solves = submission::Entity::find()
+        .filter(submission::Column::Blood.is_not_null())
+        .filter(submission::Column::BloodAnnounced.eq(false))
+        .all(db)
+        .await?;
+
+    // Solves made while the scoreboard is frozen wait until the CTF is over,
+    // so they don't give anything away
+    let event = event::Entity::find().one(db).await?;
+    let now = chrono::Utc::now().naive_utc();
+
+    for solve in solves {
+        if let Some(event) = &event {
+            if matches!(event.freeze, Some(freeze) if solve.time >= freeze) && now < event.end {
+                continue;
+            }
+        }
+
+        let (Some(team_id), Some(challenge_id)) = (solve.fk_team_id, solve.fk_challenge_id) else {
+            continue;
+        };
+
+        let team = team::Entity::find_by_id(team_id).one(db).await?;
+        let challenge = challenge::Entity::find_by_id(challenge_id).one(db).await?;
+
+        if let (Some(team), Some(challenge)) = (team, challenge) {
+            let place = match solve.blood {
+                Some(1) => "First",
+                Some(2) => "Second",
+                _ => "Third",
+            };
+
+            if let Err(why) = channel
+                .say(
+                    http,
+                    format!(
+                        "🩸 {} blood on **{}** ({}) goes to **{}**!",
+                        place, challenge.title, challenge.category, team.name
+                    ),
+                )
+                .await
+            {
+                // Try again next time
+                println!("Failed to announce first blood: {}", why);
+                continue;
+            }
+        }
+
+        // Don't announce it again
+        let mut solve: submission::ActiveModel = solve.into();
+        solve.blood_announced = Set(true);
+        solve.update(db).await?;
+    }
+
+    Ok(())
+}
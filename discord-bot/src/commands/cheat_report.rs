@@ -0,0 +1,76 @@
+use common::ctf_message::{CTFState, CheatReason};
+use entity::entities::{hacker, sea_orm_active_enums::Role};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::prelude::interaction::application_command::CommandDataOption;
+use serenity::model::prelude::UserId;
+
+pub const COMMAND_NAME: &str = "cheat_report";
+
+/// Discord won't send messages longer than this
+const MAX_MESSAGE_LENGTH: usize = 2000;
+
+pub async fn run(
+    _options: &[CommandDataOption],
+    db: DatabaseConnection,
+    user_id: UserId,
+) -> String {
+    // Only organizers get to see who's been sharing flags
+    let role = match hacker::Entity::find()
+        .filter(hacker::Column::DiscordId.eq(user_id.0 as i64))
+        .one(&db)
+        .await
+    {
+        Ok(hacker) => hacker.map(|hacker| hacker.role),
+        Err(e) => {
+            println!("Failed to get hacker: {}", e);
+            return "Couldn't check your role, try again in a bit".to_string();
+        }
+    };
+
+    if !matches!(role, Some(Role::Organizer | Role::Admin)) {
+        return "Only organizers can see the cheat report".to_string();
+    }
+
+    let suspicions = CTFState::get_cheat_suspicions(&db).await;
+    if suspicions.is_empty() {
+        return "No flag sharing has been spotted".to_string();
+    }
+
+    // Newest first, as many as fit in one message
+    let mut report = format!("# Suspected flag sharing ({})\n", suspicions.len());
+    for suspicion in &suspicions {
+        let source = match &suspicion.source_hacker {
+            Some(hacker) => format!("{} ({})", hacker, suspicion.source_team),
+            None => suspicion.source_team.clone(),
+        };
+        let reason = match suspicion.reason {
+            CheatReason::TeamFlag => "their per-team flag",
+            CheatReason::SharedSubmission => "same submission",
+        };
+
+        let line = format!(
+            "- <t:{}:f> **{}**: {} ({}) submitted `{}`, {} from {}\n",
+            suspicion.time_millis / 1000,
+            suspicion.challenge,
+            suspicion.hacker,
+            suspicion.team,
+            suspicion.flag,
+            reason,
+            source
+        );
+
+        if report.len() + line.len() > MAX_MESSAGE_LENGTH {
+            break;
+        }
+        report.push_str(&line);
+    }
+
+    report
+}
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name(COMMAND_NAME)
+        .description("List submissions that look like flags shared between teams")
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+pub mod cheat_report;
 pub mod create_interactive_prompt;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -1,5 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.3
 
+use super::sea_orm_active_enums::ScoringMode;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -8,23 +9,51 @@ use serde::{Deserialize, Serialize};
 pub struct Model {
     #[sea_orm(primary_key)]
     pub id: i32,
-    #[sea_orm(unique)]
     pub title: String,
     pub category: String,
     pub description: String,
     pub link: String,
     pub points: i32,
-    pub flag: String,
     pub author: String,
     pub active: bool,
+    pub scoring_mode: ScoringMode,
+    pub minimum_points: Option<i32>,
+    pub decay: Option<i32>,
+    #[sea_orm(unique)]
+    pub repo_path: Option<String>,
+    pub retired: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::challenge_file::Entity")]
+    ChallengeFile,
+    #[sea_orm(has_many = "super::challenge_flag::Entity")]
+    ChallengeFlag,
+    #[sea_orm(has_many = "super::hint::Entity")]
+    Hint,
     #[sea_orm(has_many = "super::submission::Entity")]
     Submission,
 }
 
+impl Related<super::challenge_file::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ChallengeFile.def()
+    }
+}
+
+impl Related<super::challenge_flag::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ChallengeFlag.def()
+    }
+}
+
+impl Related<super::hint::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Hint.def()
+    }
+}
+
 impl Related<super::submission::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Submission.def()
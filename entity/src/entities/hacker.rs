@@ -1,19 +1,30 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.3
 
+use super::sea_orm_active_enums::Role;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "hacker")]
 pub struct Model {
-    #[sea_orm(primary_key, auto_increment = false)]
-    pub discord_id: i64,
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    #[sea_orm(unique)]
     pub username: String,
     pub fk_team_id: Option<i32>,
+    #[sea_orm(default_value = "hacker")]
+    pub role: Role,
+    #[sea_orm(unique)]
+    pub discord_id: Option<i64>,
+    pub password_hash: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::audit_log::Entity")]
+    AuditLog,
+    #[sea_orm(has_many = "super::oauth_identity::Entity")]
+    OauthIdentity,
     #[sea_orm(has_many = "super::submission::Entity")]
     Submission,
     #[sea_orm(
@@ -28,6 +39,18 @@ pub enum Relation {
     Token,
 }
 
+impl Related<super::audit_log::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AuditLog.def()
+    }
+}
+
+impl Related<super::oauth_identity::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OauthIdentity.def()
+    }
+}
+
 impl Related<super::submission::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Submission.def()
@@ -2,9 +2,21 @@
 
 pub mod prelude;
 
+pub mod audit_log;
+pub mod blob;
 pub mod challenge;
+pub mod challenge_file;
+pub mod challenge_flag;
+pub mod challenge_requirement;
+pub mod cheat_suspicion;
+pub mod event;
 pub mod hacker;
+pub mod hint;
+pub mod hint_unlock;
+pub mod invite_code;
 pub mod message_component_data;
+pub mod oauth_identity;
+pub mod sea_orm_active_enums;
 pub mod submission;
 pub mod team;
 pub mod token;
@@ -1,8 +1,19 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.3
 
+pub use super::audit_log::Entity as AuditLog;
+pub use super::blob::Entity as Blob;
 pub use super::challenge::Entity as Challenge;
+pub use super::challenge_file::Entity as ChallengeFile;
+pub use super::challenge_flag::Entity as ChallengeFlag;
+pub use super::challenge_requirement::Entity as ChallengeRequirement;
+pub use super::cheat_suspicion::Entity as CheatSuspicion;
+pub use super::event::Entity as Event;
 pub use super::hacker::Entity as Hacker;
+pub use super::hint::Entity as Hint;
+pub use super::hint_unlock::Entity as HintUnlock;
+pub use super::invite_code::Entity as InviteCode;
 pub use super::message_component_data::Entity as MessageComponentData;
+pub use super::oauth_identity::Entity as OauthIdentity;
 pub use super::submission::Entity as Submission;
 pub use super::team::Entity as Team;
 pub use super::token::Entity as Token;
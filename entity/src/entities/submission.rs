@@ -14,6 +14,9 @@ pub struct Model {
     pub fk_hacker_id: Option<i64>,
     pub fk_team_id: Option<i32>,
     pub fk_challenge_id: Option<i32>,
+    pub blood: Option<i32>,
+    pub bonus_points: i32,
+    pub blood_announced: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -29,7 +32,7 @@ pub enum Relation {
     #[sea_orm(
         belongs_to = "super::hacker::Entity",
         from = "Column::FkHackerId",
-        to = "super::hacker::Column::DiscordId",
+        to = "super::hacker::Column::Id",
         on_update = "NoAction",
         on_delete = "NoAction"
     )]
@@ -10,10 +10,19 @@ pub struct Model {
     pub id: i32,
     pub name: String,
     pub join_token: String,
+    pub fk_captain_id: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::hacker::Entity",
+        from = "Column::FkCaptainId",
+        to = "super::hacker::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Captain,
     #[sea_orm(has_many = "super::hacker::Entity")]
     Hacker,
     #[sea_orm(has_many = "super::submission::Entity")]
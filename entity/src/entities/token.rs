@@ -1,5 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.3
 
+use super::sea_orm_active_enums::TokenKind;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -10,6 +11,7 @@ pub struct Model {
     pub token: String,
     pub expiry: DateTime,
     pub fk_hacker_id: Option<i64>,
+    pub kind: TokenKind,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -17,7 +19,7 @@ pub enum Relation {
     #[sea_orm(
         belongs_to = "super::hacker::Entity",
         from = "Column::FkHackerId",
-        to = "super::hacker::Column::DiscordId",
+        to = "super::hacker::Column::Id",
         on_update = "NoAction",
         on_delete = "NoAction"
     )]
@@ -1,6 +1,8 @@
-use sea_orm::prelude::*;
+use std::collections::HashMap;
 
-use crate::entities::{challenge, submission};
+use sea_orm::{prelude::*, sea_query::OnConflict, Set};
+
+use crate::entities::{challenge, event, hacker, submission, team};
 
 /// Get all the challenges this team has solved
 pub async fn get_team_solved_challenges(
@@ -57,3 +59,97 @@ pub async fn get_team_unsolved_challenges(
         })
         .collect::<Vec<challenge::Model>>()
 }
+
+/// Get the schedule of the CTF, if one has been set
+pub async fn get_event(db: &DatabaseConnection) -> Option<event::Model> {
+    event::Entity::find()
+        .one(db)
+        .await
+        .expect("Failed to get event")
+}
+
+/// Get how many teams have solved each challenge, by challenge id. Solves made
+/// at or after `before`, such as the scoreboard freeze, aren't counted.
+pub async fn get_challenge_solve_counts(
+    db: &DatabaseConnection,
+    before: Option<DateTime>,
+) -> HashMap<i32, u64> {
+    let mut solves = submission::Entity::find().filter(submission::Column::Correct.eq(true));
+    if let Some(before) = before {
+        solves = solves.filter(submission::Column::Time.lt(before));
+    }
+
+    let mut solve_counts = HashMap::new();
+    for solve in solves.all(db).await.expect("Failed to get all submissions") {
+        if let Some(challenge_id) = solve.fk_challenge_id {
+            *solve_counts.entry(challenge_id).or_insert(0) += 1;
+        }
+    }
+
+    solve_counts
+}
+
+/// Get the first blood solve on each challenge and the team that made it, by
+/// challenge id
+pub async fn get_first_bloods(
+    db: &DatabaseConnection,
+) -> HashMap<i32, (submission::Model, team::Model)> {
+    submission::Entity::find()
+        .filter(submission::Column::Blood.eq(1))
+        .find_also_related(team::Entity)
+        .all(db)
+        .await
+        .expect("Failed to get first bloods")
+        .into_iter()
+        .filter_map(|(solve, team)| Some((solve.fk_challenge_id?, (solve, team?))))
+        .collect()
+}
+
+/// Add a hacker, unless someone already has their username. Returns `None` if
+/// the name is taken.
+pub async fn insert_hacker<C: ConnectionTrait>(
+    db: &C,
+    hacker: hacker::ActiveModel,
+) -> Result<Option<hacker::Model>, DbErr> {
+    let inserted = hacker::Entity::insert(hacker)
+        .on_conflict(
+            OnConflict::column(hacker::Column::Username)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec(db)
+        .await;
+
+    match inserted {
+        Ok(inserted) => {
+            hacker::Entity::find_by_id(inserted.last_insert_id)
+                .one(db)
+                .await
+        }
+        Err(DbErr::RecordNotInserted) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Add a hacker whose username came from somewhere else, like Discord. If
+/// someone already has it, a number is put on the end until it's free.
+pub async fn insert_hacker_with_free_username<C: ConnectionTrait>(
+    db: &C,
+    username: String,
+    mut hacker: hacker::ActiveModel,
+) -> Result<hacker::Model, DbErr> {
+    let mut attempt = 1;
+    loop {
+        hacker.username = Set(if attempt == 1 {
+            username.clone()
+        } else {
+            format!("{}{}", username, attempt)
+        });
+
+        if let Some(hacker) = insert_hacker(db, hacker.clone()).await? {
+            return Ok(hacker);
+        }
+
+        attempt += 1;
+    }
+}
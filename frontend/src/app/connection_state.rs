@@ -1,4 +1,7 @@
-use common::NetworkMessage;
+use common::{
+    ctf_message::ClientMessage,
+    encoding::{Encoding, Frame},
+};
 
 use ewebsock::{WsMessage, WsReceiver, WsSender};
 use wasm_timer::Instant;
@@ -18,6 +21,7 @@ impl Default for ConnectionState {
         Self {
             inner: Arc::new(Mutex::new(ConnectionStateInner {
                 connection_state: ConnectionStateEnum::Disconnected,
+                encoding: Encoding::Cbor,
                 message_queue: Vec::new(),
                 ws_sender: None,
                 ws_receiver: None,
@@ -29,14 +33,17 @@ impl Default for ConnectionState {
 
 pub struct ConnectionStateInner {
     pub connection_state: ConnectionStateEnum,
-    pub message_queue: Vec<NetworkMessage>,
+    /// How messages are encoded on the websocket. CBOR is a lot smaller than
+    /// JSON for the big state updates.
+    pub encoding: Encoding,
+    pub message_queue: Vec<ClientMessage>,
     pub ws_sender: Option<WsSender>,
     pub ws_receiver: Option<WsReceiver>,
     pub last_connection_attempt: Option<Instant>,
 }
 
 impl ConnectionState {
-    pub fn send_message(&mut self, message: NetworkMessage) {
+    pub fn send_message(&mut self, message: ClientMessage) {
         // If we're connected to the backend, send the message right away. If
         // we're connecting or disconnected, queue the message to be sent when
         // we connect.
@@ -54,6 +61,16 @@ impl ConnectionState {
         self.process_message_queue();
     }
 
+    /// Send these messages before anything else that's queued, for the ones
+    /// that have to start off a new connection
+    pub fn send_messages_first(&mut self, messages: Vec<ClientMessage>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.message_queue.splice(0..0, messages);
+        drop(inner);
+
+        self.process_message_queue();
+    }
+
     // Try to empty the queue of messages to send to the backend. This may or
     // may not send messages.
     pub fn process_message_queue(&mut self) {
@@ -71,11 +88,11 @@ impl ConnectionState {
                 inner.message_queue.clear();
 
                 for message in messages {
-                    inner
-                        .ws_sender
-                        .as_mut()
-                        .unwrap()
-                        .send(WsMessage::Text(serde_json::to_string(&message).unwrap()));
+                    let ws_message = match inner.encoding.encode(&message) {
+                        Frame::Text(text) => WsMessage::Text(text),
+                        Frame::Binary(bytes) => WsMessage::Binary(bytes),
+                    };
+                    inner.ws_sender.as_mut().unwrap().send(ws_message);
                 }
             }
             _ => {}
@@ -104,6 +121,11 @@ impl ConnectionState {
         inner.connection_state = ConnectionStateEnum::Disconnected;
     }
 
+    pub fn get_encoding(&self) -> Encoding {
+        let inner = self.inner.lock().unwrap();
+        inner.encoding
+    }
+
     pub fn get_state(&self) -> ConnectionStateEnum {
         let inner = self.inner.lock().unwrap();
         inner.connection_state.clone()
@@ -1,6 +1,7 @@
-use common::ctf_message::CTFMessage;
+use common::ctf_message::ClientData;
+use common::ctf_message::ClientMessage;
+use common::ctf_message::Role;
 use common::ctf_message::TeamData;
-use common::NetworkMessage;
 use eframe::App;
 use egui::FontFamily::Proportional;
 use egui::FontId;
@@ -57,7 +58,7 @@ pub fn ctf_ui(ctf_app: &mut CTFApp, ctx: &egui::Context, frame: &mut eframe::Fra
                         }
 
                         // If we're on a team, show the challenge info
-                        if let TeamData::OnTeam(..) = &ctf_app.client_state.ctf_state.team_data {
+                        if let TeamData::OnTeam { .. } = &ctf_app.client_state.ctf_state.team_data {
                             // Challenges window button
                             if ui.button("Challenges").clicked() {
                                 ctf_app.current_window = CTFUIWindow::Challenge;
@@ -69,6 +70,22 @@ pub fn ctf_ui(ctf_app: &mut CTFApp, ctx: &egui::Context, frame: &mut eframe::Fra
                             }
                         }
 
+                        // Organizers can look for teams sharing flags
+                        if let ClientData::LoggedIn {
+                            role: Role::Organizer | Role::Admin,
+                            ..
+                        } = &ctf_app.client_state.ctf_state.client_data
+                        {
+                            if ui.button("Flag sharing").clicked() {
+                                ctf_app.current_window = CTFUIWindow::CheatSuspicions;
+
+                                // Save to storage
+                                if let Some(storage) = frame.storage_mut() {
+                                    ctf_app.save(storage);
+                                }
+                            }
+                        }
+
                         // Logout button
                         if ui.button("Logout").clicked() {
                             ctf_app
@@ -97,6 +114,15 @@ pub fn ctf_ui(ctf_app: &mut CTFApp, ctx: &egui::Context, frame: &mut eframe::Fra
                                 ctf_app.save(storage);
                             }
                         }
+
+                        // Log out every window and device logged in as us.
+                        // The backend tells each of them, this one included.
+                        if ui.button("Logout everywhere").clicked() {
+                            ctf_app
+                                .connection_state
+                                .send_message(ClientMessage::LogoutEverywhere);
+                            ctf_app.login_panel.token = String::new();
+                        }
                     }
                 }
             });
@@ -113,32 +139,50 @@ pub fn ctf_ui(ctf_app: &mut CTFApp, ctx: &egui::Context, frame: &mut eframe::Fra
                     ui.selectable_value(&mut ctf_app.ui_theme, UiTheme::Latte, "Latte");
                 });
 
-            // TODO: Add these settings for admin/debug only
-            ui.separator();
+            // Organizers can load changed challenges without restarting the
+            // backend. The backend checks this as well.
+            if let ClientData::LoggedIn {
+                role: Role::Organizer | Role::Admin,
+                ..
+            } = &ctf_app.client_state.ctf_state.client_data
+            {
+                ui.separator();
+
+                if ui.button("Reload challenges").clicked() {
+                    ctf_app
+                        .connection_state
+                        .send_message(ClientMessage::ReloadChallenges);
+                }
+            }
 
-            // Reset database
-            if ui.button("Reset DB and spawn teams").clicked() {
-                // Send a message to the backend to wipe the db and rerun
-                // migrations
-                ctf_app
-                    .connection_state
-                    .send_message(NetworkMessage::CTFMessage(CTFMessage::ResetDB));
+            // Only show the admin settings to admins. The backend checks this
+            // as well.
+            if let ClientData::LoggedIn {
+                role: Role::Admin, ..
+            } = &ctf_app.client_state.ctf_state.client_data
+            {
+                ui.separator();
+
+                // Reset database
+                if ui.button("Reset DB and spawn teams").clicked() {
+                    // Send a message to the backend to wipe the db and rerun
+                    // migrations
+                    ctf_app
+                        .connection_state
+                        .send_message(ClientMessage::ResetDB);
+                }
             }
 
             // // Clone the repo
             // if ui.button("Clone Repo").clicked() {
             //     // Send a message to the backend to clone the repo
-            //     ctf_app
-            //         .connection_state
-            //         .send_message(NetworkMessage::CTFMessage(CTFMessage::CloneRepo));
+            //     ctf_app.connection_state.send_message(ClientMessage::CloneRepo);
             // }
 
             // // Spawn 1000 teams
             // if ui.button("Spawn 1000 teams").clicked() {
             //     // Send a message to the backend to spawn 1000 teams
-            //     ctf_app
-            //         .connection_state
-            //         .send_message(NetworkMessage::CTFMessage(CTFMessage::SpawnTeams));
+            //     ctf_app.connection_state.send_message(ClientMessage::SpawnTeams);
             // }
 
             ui.separator();
@@ -187,6 +231,12 @@ pub fn ctf_ui(ctf_app: &mut CTFApp, ctx: &egui::Context, frame: &mut eframe::Fra
                         // Show the scoreboard
                         ctf_app.scoreboard_panel.ui(ui, &ctf_app.client_state);
                     }
+                    CTFUIWindow::CheatSuspicions => {
+                        // Show the suspected flag sharing
+                        ctf_app
+                            .cheat_suspicions_panel
+                            .ui(ui, &mut ctf_app.connection_state);
+                    }
                 }
             }
         });
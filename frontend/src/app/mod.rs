@@ -1,15 +1,20 @@
 use crate::app::ctf_ui::ctf_ui;
 use common::{
-    ctf_message::{CTFClientState, CTFClientStateComponent, CTFMessage, ClientUpdate},
-    NetworkMessage,
+    ctf_message::{
+        CTFClientState, CTFClientStateComponent, ClientMessage, ClientUpdate, ServerError,
+        ServerMessage,
+    },
+    encoding::Frame,
+    SessionId, PROTOCOL_VERSION,
 };
 use core::fmt::Display;
 use egui_notify::Toasts;
 use ewebsock::{WsEvent, WsMessage};
 use log::info;
 use panels::{
-    challenge_list::ChallengeList, challenge_panel::ChallengePanel, hacker_list::HackerList,
-    login::LoginPanel, scoreboard::ScoreboardPanel, team::TeamPanel,
+    challenge_list::ChallengeList, challenge_panel::ChallengePanel,
+    cheat_suspicions::CheatSuspicionsPanel, hacker_list::HackerList, login::LoginPanel,
+    scoreboard::ScoreboardPanel, team::TeamPanel,
 };
 use serde::{Deserialize, Serialize};
 use std::{
@@ -21,7 +26,7 @@ use wasm_timer::Instant;
 
 use self::{
     connection_state::{ConnectionState, ConnectionStateEnum},
-    panels::connecting::ConnectingPanel,
+    panels::{challenge_panel::file_url, connecting::ConnectingPanel},
 };
 
 mod connection_state;
@@ -50,6 +55,9 @@ pub struct CTFApp {
 
     connecting_panel: ConnectingPanel,
 
+    #[serde(skip)]
+    cheat_suspicions_panel: CheatSuspicionsPanel,
+
     // Other visuals
     #[serde(skip)]
     toasts: Toasts,
@@ -72,6 +80,11 @@ pub struct CTFApp {
     #[serde(skip)]
     websocket_thread_handle: Option<std::thread::JoinHandle<()>>,
 
+    // The server told us it speaks a different protocol version, so there's
+    // no point reconnecting until the page is reloaded
+    #[serde(skip)]
+    incompatible_version: bool,
+
     client_state: ClientState,
 
     ui_theme: UiTheme,
@@ -85,6 +98,7 @@ pub enum CTFUIWindow {
     Team,
     Challenge,
     Scoreboard,
+    CheatSuspicions,
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq)]
@@ -99,6 +113,10 @@ pub enum UiTheme {
 pub struct ClientState {
     // pub credentials: Option<Credentials>,
     pub ctf_state: CTFClientState,
+    // Set when we've asked the server for all of the GlobalData again, so we
+    // don't ask over and over while waiting for it.
+    #[serde(skip)]
+    pub global_data_requested: bool,
 }
 
 pub struct WSStateQueue {
@@ -111,6 +129,11 @@ pub struct AuthenticationState {
 
     #[serde(skip)]
     state: AuthenticationStateEnum,
+
+    // Lets us pick the session back up if the connection drops, without
+    // logging in again
+    #[serde(skip)]
+    session_id: Option<SessionId>,
 }
 
 impl Default for AuthenticationState {
@@ -118,17 +141,28 @@ impl Default for AuthenticationState {
         Self {
             valid_token: None,
             state: AuthenticationStateEnum::NotAuthenticated,
+            session_id: None,
         }
     }
 }
 
 impl AuthenticationState {
+    /// If we already have a valid login token, send it to the backend to auth
+    /// this connection. Otherwise, send a unauthenticated connect message.
+    fn login_message(&self) -> ClientMessage {
+        match &self.valid_token {
+            Some(token) => ClientMessage::Login(token.to_owned()),
+            None => ClientMessage::Connect,
+        }
+    }
+
     pub fn logout(&mut self, connection_state: &mut ConnectionState) {
         self.valid_token = None;
         self.state = AuthenticationStateEnum::NotAuthenticated;
+        self.session_id = None;
 
         // Send a logout message to the backend
-        connection_state.send_message(NetworkMessage::CTFMessage(CTFMessage::Logout));
+        connection_state.send_message(ClientMessage::Logout);
     }
 }
 
@@ -156,6 +190,63 @@ impl Display for ConnectionStateError {
     }
 }
 
+/// Where the backend answers plain HTTP requests. It serves them from the same
+/// address as the websocket.
+pub fn backend_http_url() -> String {
+    include_str!("backend_domain.txt")
+        .trim()
+        .trim_end_matches("/ws")
+        .replacen("ws", "http", 1)
+}
+
+/// Take the `#...` off the page's URL without reloading it or adding to the
+/// history
+#[cfg(target_arch = "wasm32")]
+fn clear_url_fragment() {
+    let window = match web_sys::window() {
+        Some(window) => window,
+        None => return,
+    };
+    let location = window.location();
+    let url = match (location.pathname(), location.search()) {
+        (Ok(pathname), Ok(search)) => format!("{}{}", pathname, search),
+        _ => return,
+    };
+
+    if let Ok(history) = window.history() {
+        let _ = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url));
+    }
+}
+
+/// Undo the percent-encoding of part of a URL
+#[cfg(target_arch = "wasm32")]
+fn decode_url_fragment(text: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = text.as_bytes();
+
+    while let Some((&byte, after)) = rest.split_first() {
+        let decoded = match (byte, after) {
+            (b'%', [high, low, ..]) => std::str::from_utf8(&[*high, *low])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+
+        match decoded {
+            Some(decoded) => {
+                bytes.push(decoded);
+                rest = &after[2..];
+            }
+            None => {
+                bytes.push(byte);
+                rest = after;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
 impl Default for CTFApp {
     fn default() -> Self {
         Self {
@@ -167,15 +258,18 @@ impl Default for CTFApp {
             challenge_panel: ChallengePanel::default(),
             scoreboard_panel: ScoreboardPanel::default(),
             connecting_panel: ConnectingPanel::default(),
+            cheat_suspicions_panel: CheatSuspicionsPanel::default(),
             // Other visuals
             toasts: Toasts::default(),
             // Other state
             websocket_thread_handle: None,
+            incompatible_version: false,
             connection_state: ConnectionState::default(),
             ws_state_queue: Arc::new(Mutex::new(WSStateQueue { queue: Vec::new() })),
             authentication_state: AuthenticationState::default(),
             client_state: ClientState {
                 ctf_state: CTFClientState::default(),
+                global_data_requested: false,
             },
             ui_theme: UiTheme::Frappe,
             current_window: CTFUIWindow::Scoreboard,
@@ -187,14 +281,41 @@ impl CTFApp {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
-        }
+        #[allow(unused_mut)]
+        let mut app: Self = match cc.storage {
+            Some(storage) => eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default(),
+            None => Self {
+                websocket_thread_handle: None,
+                ..Default::default()
+            },
+        };
 
-        Self {
-            websocket_thread_handle: None,
-            ..Default::default()
+        // Coming back from logging in with OAuth, the backend puts our token,
+        // or what went wrong, after the #
+        #[cfg(target_arch = "wasm32")]
+        app.take_oauth_redirect(&cc.integration_info.web_info.location.hash);
+
+        app
+    }
+
+    /// Log in with the token the backend sent us back with, which happens as
+    /// soon as we connect
+    #[cfg(target_arch = "wasm32")]
+    fn take_oauth_redirect(&mut self, hash: &str) {
+        if let Some(token) = hash.strip_prefix("#token=") {
+            self.authentication_state.valid_token = Some(token.to_string());
+            self.authentication_state.session_id = None;
+        } else if let Some(error) = hash.strip_prefix("#error=") {
+            self.toasts
+                .error(decode_url_fragment(error))
+                .set_duration(Some(Duration::from_secs(10)));
+        } else {
+            return;
         }
+
+        // The token shouldn't be left in the address bar, or in the history
+        // for anyone to find later
+        clear_url_fragment();
     }
 
     fn connect(&mut self, ctx: egui::Context) {
@@ -229,27 +350,35 @@ impl CTFApp {
         };
 
         // TODO: I don't know if there's a better way to hardcode the domain in,
-        // since I can't ship an envfile with the frontend I think
-        match ewebsock::connect_with_wakeup(include_str!("backend_domain.txt"), wakeup) {
+        // since I can't ship an envfile with the frontend I think. The query
+        // string tells the backend how we want messages encoded.
+        let url = format!(
+            "{}?{}",
+            include_str!("backend_domain.txt").trim(),
+            self.connection_state.get_encoding().query()
+        );
+        match ewebsock::connect_with_wakeup(&url, wakeup) {
             Ok((ws_sender, ws_receiver)) => {
                 self.connection_state
                     .set_state_connected(ws_sender, ws_receiver);
 
                 info!("Auth status: {:?}", &self.authentication_state);
 
-                // If we already have a valid login token, send it to the
-                // backend to auth this connection
-                if let Some(token) = &self.authentication_state.valid_token {
-                    self.connection_state
-                        .send_message(NetworkMessage::CTFMessage(CTFMessage::Login(
-                            token.to_owned(),
-                        )));
-                }
-                // Otherwise, send a unauthenticated connect message
-                else {
-                    self.connection_state
-                        .send_message(NetworkMessage::CTFMessage(CTFMessage::Connect));
-                }
+                // Tell the backend which protocol we speak before anything
+                // else, so it can turn us away if it doesn't match. If we had
+                // a session before the connection dropped, ask for it back,
+                // otherwise start a new one. These go ahead of anything that
+                // was queued while we were disconnected.
+                let session_message = match self.authentication_state.session_id {
+                    Some(session_id) => ClientMessage::Resume(session_id),
+                    None => self.authentication_state.login_message(),
+                };
+                self.connection_state.send_messages_first(vec![
+                    ClientMessage::Hello {
+                        protocol_version: PROTOCOL_VERSION,
+                    },
+                    session_message,
+                ]);
             }
             Err(error) => {
                 panic!("Failed to connect {}", error);
@@ -291,10 +420,14 @@ impl eframe::App for CTFApp {
         self.connection_state.process_message_queue();
 
         let mut save_flag = false;
+        let mut request_global_data = false;
+        let mut log_in_again = false;
 
         match &self.connection_state.get_state() {
             ConnectionStateEnum::Disconnected => {
-                self.connect(ctx.clone());
+                if !self.incompatible_version {
+                    self.connect(ctx.clone());
+                }
             }
             ConnectionStateEnum::Opened => {
                 // Check for any messages from the server
@@ -308,105 +441,254 @@ impl eframe::App for CTFApp {
                     .unwrap()
                     .try_recv()
                 {
-                    if let WsEvent::Message(WsMessage::Text(ws_text)) = event {
-                        // Deserialize the message
-                        let message: NetworkMessage = serde_json::from_str(&ws_text).unwrap();
-
-                        // // Debug the state of the app
-                        // info!("App state: {:?}", self.client_state);
-                        // // info!("Connection state: {:?}", self.connection_state);
-                        // info!("Auth state: {:?}", self.authentication_state);
-                        // info!("Message: {:?}", message);
-
-                        match message {
-                            // All messages about the CTF game
-                            NetworkMessage::CTFMessage(ctf_message) => {
-                                match ctf_message {
-                                    // If we get a state update from the server
-                                    CTFMessage::CTFClientStateComponent(
-                                        ctf_client_state_component,
-                                    ) => match ctf_client_state_component {
-                                        CTFClientStateComponent::GlobalData(global_data) => {
-                                            self.client_state.ctf_state.global_data =
-                                                Some(global_data);
-                                        }
-                                        CTFClientStateComponent::GameData(game_data) => {
-                                            self.client_state.ctf_state.game_data = game_data;
-                                        }
-                                        CTFClientStateComponent::TeamData(team_data) => {
-                                            self.client_state.ctf_state.team_data = team_data;
-                                        }
-                                        CTFClientStateComponent::ClientData(client_data) => {
-                                            self.client_state.ctf_state.client_data = client_data;
-                                        }
-                                    },
-
-                                    // Events that the server sends and we
-                                    // should display
-                                    CTFMessage::ClientUpdate(event) => match event {
-                                        ClientUpdate::ScoredPoint(string) => {
-                                            self.toasts
-                                                .info(string)
-                                                .set_duration(Some(Duration::from_secs(5)));
-                                        }
-                                        ClientUpdate::TeamScoredPoint => todo!(),
-                                        ClientUpdate::IncorrectFlag(string) => {
-                                            self.toasts
-                                                .error(string)
-                                                .set_duration(Some(Duration::from_secs(5)));
-                                        }
-                                        ClientUpdate::Authenticated {
-                                            discord_username,
-                                            valid_token,
-                                        } => {
-                                            self.toasts
-                                                .info(format!("Logged in as {}", discord_username))
-                                                .set_duration(Some(Duration::from_secs(5)));
-
-                                            // Set the authentication state
-                                            self.authentication_state = AuthenticationState {
-                                                valid_token: Some(valid_token),
-                                                state: AuthenticationStateEnum::Authenticated,
-                                            };
-
-                                            // Change the screen back to the
-                                            // scoreboard
-                                            self.current_window = CTFUIWindow::Scoreboard;
-
-                                            // Flag to save the app state
-                                            save_flag = true;
-                                        }
-                                        ClientUpdate::IncorrectToken => {
-                                            self.toasts
-                                                .error("Incorrect token")
-                                                .set_duration(Some(Duration::from_secs(5)));
+                    let frame = match event {
+                        WsEvent::Message(WsMessage::Text(text)) => Frame::Text(text),
+                        WsEvent::Message(WsMessage::Binary(bytes)) => Frame::Binary(bytes),
+                        _ => continue,
+                    };
+
+                    // Deserialize the message
+                    let message: ServerMessage = match frame.decode() {
+                        Ok(message) => message,
+                        Err(e) => {
+                            log::error!("{}", e);
+                            continue;
+                        }
+                    };
+
+                    // // Debug the state of the app
+                    // info!("App state: {:?}", self.client_state);
+                    // // info!("Connection state: {:?}", self.connection_state);
+                    // info!("Auth state: {:?}", self.authentication_state);
+                    // info!("Message: {:?}", message);
+
+                    match message {
+                        // If we get a state update from the server
+                        ServerMessage::CTFClientStateComponent(ctf_client_state_component) => {
+                            match ctf_client_state_component {
+                                CTFClientStateComponent::GlobalData(global_data) => {
+                                    self.client_state.ctf_state.global_data = Some(global_data);
+                                    self.client_state.global_data_requested = false;
+                                }
+                                CTFClientStateComponent::GlobalDataUpdate { seq, update } => {
+                                    // Until the first GlobalData shows
+                                    // up, there's nothing to update
+                                    if let Some(global_data) =
+                                        &mut self.client_state.ctf_state.global_data
+                                    {
+                                        // The challenge list follows
+                                        // rescores too, even if some
+                                        // updates were missed
+                                        if seq > global_data.seq {
+                                            self.client_state.ctf_state.game_data.apply(&update);
                                         }
-                                        ClientUpdate::Notification(notification) => {
-                                            self.toasts
-                                                .info(notification)
-                                                .set_duration(Some(Duration::from_secs(5)));
+
+                                        if seq == global_data.seq + 1 {
+                                            global_data.apply(&update);
+                                            global_data.seq = seq;
+                                        } else if seq > global_data.seq
+                                            && !self.client_state.global_data_requested
+                                        {
+                                            // We missed an update, so
+                                            // ask for all of it again
+                                            self.client_state.global_data_requested = true;
+                                            request_global_data = true;
                                         }
-                                    },
-
-                                    // The client can't receive any of these
-                                    // messages
-
-                                    // TODO: Redo the enum so that only messages
-                                    // that the client can receive are in this
-                                    // or something
-                                    CTFMessage::SubmitFlag { .. }
-                                    | CTFMessage::JoinTeam(_)
-                                    | CTFMessage::CreateTeam(_)
-                                    | CTFMessage::Login(_)
-                                    | CTFMessage::Logout
-                                    | CTFMessage::Connect
-                                    | CTFMessage::SpawnTeams
-                                    | CTFMessage::CloneRepo
-                                    | CTFMessage::ResetDB
-                                    | CTFMessage::LeaveTeam => unreachable!(),
+                                    }
+                                }
+                                CTFClientStateComponent::GameData(game_data) => {
+                                    self.client_state.ctf_state.game_data = game_data;
+                                }
+                                CTFClientStateComponent::TeamData(team_data) => {
+                                    self.client_state.ctf_state.team_data = team_data;
+                                }
+                                CTFClientStateComponent::ClientData(client_data) => {
+                                    self.client_state.ctf_state.client_data = client_data;
+                                }
+                            }
+                        }
+
+                        // Events that the server sends and we
+                        // should display
+                        ServerMessage::ClientUpdate(event) => match event {
+                            ClientUpdate::ScoredPoint(string) => {
+                                self.toasts
+                                    .info(string)
+                                    .set_duration(Some(Duration::from_secs(5)));
+                            }
+                            ClientUpdate::TeamScoredPoint => {
+                                self.toasts
+                                    .info("Your team scored a point")
+                                    .set_duration(Some(Duration::from_secs(5)));
+                            }
+                            ClientUpdate::IncorrectFlag(string) => {
+                                self.toasts
+                                    .error(string)
+                                    .set_duration(Some(Duration::from_secs(5)));
+                            }
+                            ClientUpdate::Authenticated {
+                                username,
+                                valid_token,
+                            } => {
+                                self.toasts
+                                    .info(format!("Logged in as {}", username))
+                                    .set_duration(Some(Duration::from_secs(5)));
+
+                                // The token stands in for the password now
+                                self.login_panel.password.clear();
+
+                                // Set the authentication state
+                                self.authentication_state = AuthenticationState {
+                                    valid_token: Some(valid_token),
+                                    state: AuthenticationStateEnum::Authenticated,
+                                    session_id: None,
+                                };
+
+                                // Change the screen back to the
+                                // scoreboard
+                                self.current_window = CTFUIWindow::Scoreboard;
+
+                                // Flag to save the app state
+                                save_flag = true;
+                            }
+                            ClientUpdate::IncorrectToken => {
+                                self.toasts
+                                    .error("Incorrect token")
+                                    .set_duration(Some(Duration::from_secs(5)));
+
+                                // If it was the token we had saved, it was
+                                // revoked, so stop trying it
+                                self.authentication_state.valid_token = None;
+                                save_flag = true;
+                            }
+                            ClientUpdate::LoginOptions {
+                                password_accounts,
+                                oauth_provider,
+                            } => {
+                                self.login_panel.password_accounts = password_accounts;
+                                self.login_panel.oauth_provider = oauth_provider;
+                            }
+                            ClientUpdate::IncorrectPassword => {
+                                self.toasts
+                                    .error("Incorrect username or password")
+                                    .set_duration(Some(Duration::from_secs(5)));
+                            }
+                            ClientUpdate::RegistrationFailed(reason) => {
+                                self.toasts
+                                    .error(reason)
+                                    .set_duration(Some(Duration::from_secs(5)));
+                            }
+                            ClientUpdate::TokenExpired => {
+                                self.toasts
+                                    .error("That token expired, log in again")
+                                    .set_duration(Some(Duration::from_secs(5)));
+
+                                self.authentication_state.valid_token = None;
+                                save_flag = true;
+                            }
+                            ClientUpdate::LoggedOut(reason) => {
+                                self.toasts
+                                    .info(reason)
+                                    .set_duration(Some(Duration::from_secs(5)));
+
+                                // Forget everything about the login, the
+                                // backend already has
+                                self.authentication_state = AuthenticationState::default();
+                                self.current_window = CTFUIWindow::Scoreboard;
+                                save_flag = true;
+                            }
+                            ClientUpdate::SessionStarted(session_id) => {
+                                self.authentication_state.session_id = Some(session_id);
+                            }
+                            ClientUpdate::Resumed => {
+                                info!("Resumed the session");
+                            }
+                            ClientUpdate::ResumeFailed => {
+                                // The session expired, so log in from
+                                // scratch
+                                self.authentication_state.session_id = None;
+                                log_in_again = true;
+                            }
+                            ClientUpdate::Notification(notification) => {
+                                self.toasts
+                                    .info(notification)
+                                    .set_duration(Some(Duration::from_secs(5)));
+                            }
+                            ClientUpdate::PermissionDenied(reason) => {
+                                self.toasts
+                                    .error(reason)
+                                    .set_duration(Some(Duration::from_secs(5)));
+                            }
+                            ClientUpdate::FirstBlood {
+                                team,
+                                challenge,
+                                challenge_id,
+                                blood,
+                            } => {
+                                if blood == 1 {
+                                    self.client_state
+                                        .ctf_state
+                                        .game_data
+                                        .set_first_blood(challenge_id, team.clone());
                                 }
+
+                                let place = match blood {
+                                    1 => "First",
+                                    2 => "Second",
+                                    _ => "Third",
+                                };
+                                self.toasts
+                                    .success(format!(
+                                        "{} blood on {} by {}!",
+                                        place, challenge, team
+                                    ))
+                                    .set_duration(Some(Duration::from_secs(8)));
+                            }
+                            ClientUpdate::CheatSuspicions(suspicions) => {
+                                self.cheat_suspicions_panel.suspicions = Some(suspicions);
+                            }
+                            ClientUpdate::ChallengesUnlocked(titles) => {
+                                self.toasts
+                                    .success(format!("Unlocked {}", titles.join(", ")))
+                                    .set_duration(Some(Duration::from_secs(8)));
                             }
-                            _ => {}
+                            ClientUpdate::RateLimited { retry_after } => {
+                                self.challenge_panel.rate_limited_until =
+                                    Some(Instant::now() + Duration::from_secs(retry_after));
+                                self.toasts
+                                    .warning(format!(
+                                        "Too many submissions, try again in {} seconds",
+                                        retry_after
+                                    ))
+                                    .set_duration(Some(Duration::from_secs(5)));
+                            }
+                            ClientUpdate::DownloadReady {
+                                challenge_id,
+                                name,
+                                token,
+                            } => {
+                                // The file is sent as an attachment, so the
+                                // browser downloads it and stays on this page
+                                let url = file_url(challenge_id, &name, &token);
+                                ctx.output_mut(|output| {
+                                    output.open_url = Some(egui::output::OpenUrl::same_tab(url))
+                                });
+                            }
+                        },
+
+                        // The server accepted our protocol version
+                        ServerMessage::Welcome { protocol_version } => {
+                            info!("Connected with protocol version {}", protocol_version);
+                        }
+
+                        // The server couldn't do what we asked
+                        ServerMessage::Error(error) => {
+                            if let ServerError::IncompatibleVersion { .. } = error {
+                                self.incompatible_version = true;
+                            }
+                            self.toasts
+                                .error(error.to_string())
+                                .set_duration(Some(Duration::from_secs(5)));
                         }
                     }
                 }
@@ -424,6 +706,18 @@ impl eframe::App for CTFApp {
             }
         }
 
+        // Same goes for sending messages, since the connection is locked while
+        // we're reading from it
+        if request_global_data {
+            self.connection_state
+                .send_message(ClientMessage::RequestGlobalData);
+        }
+
+        if log_in_again {
+            self.connection_state
+                .send_message(self.authentication_state.login_message());
+        }
+
         ctf_ui(self, ctx, frame);
     }
 
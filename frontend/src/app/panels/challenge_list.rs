@@ -1,4 +1,4 @@
-use common::ctf_message::{CTFChallenge, GameData};
+use common::ctf_message::{CTFChallenge, ChallengeId, GameData};
 use eframe::egui;
 use egui::{epaint::ahash::HashMap, ScrollArea};
 
@@ -10,7 +10,7 @@ use crate::app::ClientState;
 #[derive(Deserialize, Serialize, Default)]
 pub struct ChallengeList {
     // Challenge that should be displayed
-    pub visible_challenge: Option<String>,
+    pub visible_challenge: Option<ChallengeId>,
 }
 
 impl ChallengeList {
@@ -45,11 +45,29 @@ impl ChallengeList {
                     let mut challenges = challenges.clone();
                     challenges.sort_by(|a, b| a.points.cmp(&b.points));
                     for challenge in challenges {
-                        if ui
-                            .button(format!("{} ({} points)", challenge.title, challenge.points))
-                            .clicked()
-                        {
-                            self.visible_challenge = Some(challenge.title.clone());
+                        // Mark challenges that someone has drawn first blood on
+                        let text = match &challenge.first_blood {
+                            Some(_) => {
+                                format!("★ {} ({} points)", challenge.title, challenge.points)
+                            }
+                            None => format!("{} ({} points)", challenge.title, challenge.points),
+                        };
+
+                        // Locked challenges are greyed out until the team
+                        // meets their requirements
+                        let mut button = ui.add_enabled(!challenge.locked, egui::Button::new(text));
+                        if let Some(team) = &challenge.first_blood {
+                            button = button.on_hover_text(format!("First blood by {}", team));
+                        }
+                        if challenge.locked {
+                            button = button.on_disabled_hover_text(format!(
+                                "Locked. Requires:\n{}",
+                                challenge.requires.join("\n")
+                            ));
+                        }
+
+                        if button.clicked() {
+                            self.visible_challenge = Some(challenge.id);
                         }
                     }
                 }
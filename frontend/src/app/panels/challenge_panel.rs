@@ -1,17 +1,18 @@
-use common::{
-    ctf_message::{CTFMessage, GameData},
-    NetworkMessage,
-};
+use common::ctf_message::{ChallengeId, ClientMessage, GameData};
 use eframe::egui;
 
 use egui::Align2;
 use serde::{Deserialize, Serialize};
+use wasm_timer::Instant;
 
-use crate::app::{ClientState, ConnectionState};
+use crate::app::{backend_http_url, ClientState, ConnectionState};
 
 #[derive(Deserialize, Serialize, Default)]
 pub struct ChallengePanel {
     flag: String,
+    /// Set when the server says we're submitting too quickly
+    #[serde(skip)]
+    pub rate_limited_until: Option<Instant>,
 }
 
 impl ChallengePanel {
@@ -23,7 +24,7 @@ impl ChallengePanel {
         &mut self,
         ctx: &egui::Context,
         ctf_state: &ClientState,
-        visible_challenge: &Option<String>,
+        visible_challenge: &Option<ChallengeId>,
         connection_state: &mut ConnectionState,
     ) {
         egui::Window::new(self.name())
@@ -40,18 +41,28 @@ impl ChallengePanel {
         &mut self,
         ui: &mut egui::Ui,
         ctf_state: &ClientState,
-        visible_challenge: &Option<String>,
+        visible_challenge: &Option<ChallengeId>,
         connection_state: &mut ConnectionState,
     ) {
-        if let Some(challenge_name) = visible_challenge {
-            // Check if there is a challenge with this name in the game state
+        if let Some(challenge_id) = visible_challenge {
+            // Check if there is a challenge with this id in the game state
             if let GameData::LoggedIn { challenges } = &ctf_state.ctf_state.game_data {
-                if let Some(challenge) = challenges.iter().find(|c| &c.title == challenge_name) {
+                if let Some(challenge) = challenges.iter().find(|c| &c.id == challenge_id) {
                     // Show the challenge
                     ui.heading(&challenge.title);
                     ui.label(&format!("Category: {}", challenge.category));
                     ui.label(&format!("Points: {}", challenge.points));
                     ui.separator();
+
+                    // There's nothing to see until the team unlocks it
+                    if challenge.locked {
+                        ui.label("This challenge is locked. To unlock it:");
+                        for requirement in &challenge.requires {
+                            ui.label(format!("• {}", requirement));
+                        }
+                        return;
+                    }
+
                     if ui.label(&challenge.description).clicked() {
                         ui.output_mut(|o| {
                             o.copied_text = challenge.description.clone();
@@ -69,6 +80,61 @@ impl ChallengePanel {
                         ui.hyperlink(&challenge.link);
                     }
 
+                    // Files are downloaded straight from the backend, with a
+                    // token the backend sends back for each one
+                    if !challenge.files.is_empty() {
+                        ui.separator();
+                        ui.label("Files:");
+                        for file in &challenge.files {
+                            if ui
+                                .link(format!("{} ({})", file.name, format_size(file.size)))
+                                .on_hover_text(format!("SHA-256: {}", file.sha256))
+                                .clicked()
+                            {
+                                connection_state.send_message(ClientMessage::RequestDownload {
+                                    challenge_id: challenge.id,
+                                    name: file.name.clone(),
+                                });
+                            }
+                        }
+                    }
+
+                    // Hints show up for the whole team once anyone on it
+                    // unlocks them
+                    if !challenge.hints.is_empty() {
+                        ui.separator();
+                        ui.label("Hints:");
+                        for (i, hint) in challenge.hints.iter().enumerate() {
+                            match &hint.text {
+                                Some(text) => {
+                                    ui.label(format!("{}. {}", i + 1, text));
+                                }
+                                None => {
+                                    // Hints have to be unlocked in order
+                                    let next = challenge.hints[..i]
+                                        .iter()
+                                        .all(|earlier| earlier.text.is_some());
+                                    if ui
+                                        .add_enabled(
+                                            next,
+                                            egui::Button::new(format!(
+                                                "Unlock hint {} (-{} points)",
+                                                i + 1,
+                                                hint.cost
+                                            )),
+                                        )
+                                        .clicked()
+                                    {
+                                        connection_state.send_message(ClientMessage::UnlockHint {
+                                            challenge_id: challenge.id,
+                                            hint: i,
+                                        });
+                                    }
+                                }
+                            }
+                        }
+                    }
+
                     ui.separator();
 
                     // Login form
@@ -77,16 +143,32 @@ impl ChallengePanel {
                         ui.text_edit_singleline(&mut self.flag);
                     });
 
+                    // Don't let anyone submit until the server will accept it
+                    let now = Instant::now();
+                    let wait = self
+                        .rate_limited_until
+                        .filter(|until| *until > now)
+                        .map(|until| until - now);
+                    let submit_text = match wait {
+                        Some(wait) => {
+                            ui.ctx()
+                                .request_repaint_after(std::time::Duration::from_secs(1));
+                            format!("Submit ({}s)", wait.as_secs() + 1)
+                        }
+                        None => "Submit".to_string(),
+                    };
+
                     // Submit button
-                    if ui.button("Submit").clicked() {
+                    if ui
+                        .add_enabled(wait.is_none(), egui::Button::new(submit_text))
+                        .clicked()
+                    {
                         // Send the submission to the server if it's not empty
                         if !self.flag.is_empty() {
-                            connection_state.send_message(NetworkMessage::CTFMessage(
-                                CTFMessage::SubmitFlag {
-                                    challenge_name: challenge_name.clone(),
-                                    flag: self.flag.clone(),
-                                },
-                            ));
+                            connection_state.send_message(ClientMessage::SubmitFlag {
+                                challenge_id: challenge.id,
+                                flag: self.flag.clone(),
+                            });
                         }
                     }
                 }
@@ -94,3 +176,47 @@ impl ChallengePanel {
         }
     }
 }
+
+/// Where to download a challenge's file from, with the download token the
+/// backend gave us for it
+pub fn file_url(challenge: ChallengeId, name: &str, download_token: &str) -> String {
+    format!(
+        "{}/files/{}/{}?download={}",
+        backend_http_url(),
+        challenge,
+        name.split('/')
+            .map(encode_url_path)
+            .collect::<Vec<_>>()
+            .join("/"),
+        download_token
+    )
+}
+
+/// Percent-encode anything that isn't safe in part of a URL path
+fn encode_url_path(part: &str) -> String {
+    part.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Format a number of bytes, such as "1.5 MB"
+fn format_size(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, units[unit])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}
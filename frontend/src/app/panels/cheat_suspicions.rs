@@ -0,0 +1,116 @@
+use common::ctf_message::{CheatReason, CheatSuspicion, ClientMessage};
+use eframe::egui;
+use egui_extras::{Column, TableBuilder};
+use wasm_timer::SystemTime;
+
+use crate::app::ConnectionState;
+
+/// Organizer view of wrong submissions that look like they were shared
+/// between teams
+#[derive(Default)]
+pub struct CheatSuspicionsPanel {
+    // The last list the server sent us
+    pub suspicions: Option<Vec<CheatSuspicion>>,
+}
+
+impl CheatSuspicionsPanel {
+    fn name(&self) -> &'static str {
+        "Flag sharing"
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, connection_state: &mut ConnectionState) {
+        ui.heading(self.name());
+
+        // Ask for the list the first time the panel is opened, and whenever
+        // someone wants it refreshed
+        if self.suspicions.is_none() || ui.button("Refresh").clicked() {
+            self.suspicions = Some(Vec::new());
+            connection_state.send_message(ClientMessage::RequestCheatSuspicions);
+        }
+
+        ui.separator();
+
+        let suspicions = self.suspicions.as_deref().unwrap_or_default();
+        if suspicions.is_empty() {
+            ui.label("No flag sharing has been spotted");
+            return;
+        }
+
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .columns(Column::auto().resizable(true), 6)
+            .column(Column::remainder().clip(true))
+            .header(20.0, |mut header| {
+                for title in [
+                    "When",
+                    "Challenge",
+                    "Submitted by",
+                    "Looks like it came from",
+                    "Reason",
+                    "Flag",
+                ] {
+                    header.col(|ui| {
+                        ui.strong(title);
+                    });
+                }
+                header.col(|_| ());
+            })
+            .body(|mut body| {
+                for suspicion in suspicions {
+                    body.row(18.0, |mut row| {
+                        row.col(|ui| {
+                            ui.label(format_ago(now.saturating_sub(suspicion.time_millis)));
+                        });
+                        row.col(|ui| {
+                            ui.label(&suspicion.challenge);
+                        });
+                        row.col(|ui| {
+                            ui.label(format!("{} ({})", suspicion.hacker, suspicion.team));
+                        });
+                        row.col(|ui| {
+                            ui.label(match &suspicion.source_hacker {
+                                Some(hacker) => format!("{} ({})", hacker, suspicion.source_team),
+                                None => suspicion.source_team.clone(),
+                            });
+                        });
+                        row.col(|ui| {
+                            ui.label(match suspicion.reason {
+                                CheatReason::TeamFlag => "Their per-team flag",
+                                CheatReason::SharedSubmission => "Same submission",
+                            });
+                        });
+                        row.col(|ui| {
+                            ui.monospace(&suspicion.flag);
+                        });
+                        row.col(|ui| {
+                            // Stop them from using their session while this
+                            // is looked into
+                            if ui.button("Log out").clicked() {
+                                connection_state.send_message(ClientMessage::RevokeHacker(
+                                    suspicion.hacker.clone(),
+                                ));
+                            }
+                        });
+                    });
+                }
+            });
+    }
+}
+
+/// How long ago something happened, such as "5m ago"
+fn format_ago(millis: u128) -> String {
+    let seconds = millis / 1000;
+    if seconds < 60 {
+        format!("{}s ago", seconds)
+    } else if seconds < 60 * 60 {
+        format!("{}m ago", seconds / 60)
+    } else {
+        format!("{}h {}m ago", seconds / 3600, (seconds / 60) % 60)
+    }
+}
@@ -1,15 +1,39 @@
-use common::{ctf_message::CTFMessage, NetworkMessage};
+use common::ctf_message::ClientMessage;
 use egui::Align2;
 
-use crate::app::ConnectionState;
+use crate::app::{backend_http_url, ConnectionState};
+
+/// How the hacker is logging in
+#[derive(PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum LoginMode {
+    /// With a token from the Discord bot
+    Token,
+    /// With the username and password of an account they registered
+    Password,
+    /// Making an account with an invite code
+    Register,
+}
 
 /// Shows off one example of each major type of widget.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[serde(default)]
 pub struct LoginPanel {
     enabled: bool,
     visible: bool,
+    mode: LoginMode,
     pub token: String,
+    username: String,
+    /// Never saved, unlike everything else here
+    #[serde(skip)]
+    pub password: String,
+    #[serde(skip)]
+    invite_code: String,
+    /// What the server lets us log in with, which it tells us when we connect
+    #[serde(skip)]
+    pub password_accounts: bool,
+    #[serde(skip)]
+    pub oauth_provider: Option<String>,
 }
 
 impl Default for LoginPanel {
@@ -17,7 +41,13 @@ impl Default for LoginPanel {
         Self {
             enabled: true,
             visible: true,
+            mode: LoginMode::Token,
             token: String::new(),
+            username: String::new(),
+            password: String::new(),
+            invite_code: String::new(),
+            password_accounts: false,
+            oauth_provider: None,
         }
     }
 }
@@ -44,20 +74,93 @@ impl LoginPanel {
         ui.add_enabled_ui(self.enabled, |ui| {
             ui.set_visible(self.visible);
 
+            // Logging in with the provider happens on its own site, which
+            // sends us back here with a token
+            if let Some(provider) = &self.oauth_provider {
+                if ui.button(format!("Log in with {}", provider)).clicked() {
+                    let url = format!("{}/oauth/login", backend_http_url());
+                    ui.ctx().output_mut(|output| {
+                        output.open_url = Some(egui::output::OpenUrl::same_tab(url))
+                    });
+                }
+
+                ui.separator();
+            }
+
+            // Pick how to log in
+            if self.password_accounts {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut self.mode, LoginMode::Token, "Discord token");
+                    ui.selectable_value(&mut self.mode, LoginMode::Password, "Password");
+                    ui.selectable_value(&mut self.mode, LoginMode::Register, "Register");
+                });
+
+                ui.separator();
+            } else {
+                self.mode = LoginMode::Token;
+            }
+
             // Login form
-            ui.horizontal(|ui| {
-                ui.label("Token:");
-                ui.text_edit_singleline(&mut self.token);
-            });
+            match self.mode {
+                LoginMode::Token => {
+                    ui.horizontal(|ui| {
+                        ui.label("Token:");
+                        ui.text_edit_singleline(&mut self.token);
+                    });
+                }
+                LoginMode::Password | LoginMode::Register => {
+                    ui.horizontal(|ui| {
+                        ui.label("Username:");
+                        ui.text_edit_singleline(&mut self.username);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Password:");
+                        ui.add(egui::TextEdit::singleline(&mut self.password).password(true));
+                    });
+                }
+            }
+
+            if self.mode == LoginMode::Register {
+                ui.horizontal(|ui| {
+                    ui.label("Invite code:");
+                    ui.text_edit_singleline(&mut self.invite_code);
+                });
+            }
         });
 
         // Login button
-        if ui.button("Login").clicked() {
+        let button = match self.mode {
+            LoginMode::Register => "Register",
+            LoginMode::Token | LoginMode::Password => "Login",
+        };
+        if ui.button(button).clicked() {
             // Send the submission to the server if it's not empty
-            if !self.token.is_empty() {
-                connection_state.send_message(NetworkMessage::CTFMessage(CTFMessage::Login(
-                    self.token.clone(),
-                )));
+            let message = match self.mode {
+                LoginMode::Token if !self.token.is_empty() => {
+                    Some(ClientMessage::Login(self.token.clone()))
+                }
+                LoginMode::Password if !self.username.is_empty() && !self.password.is_empty() => {
+                    Some(ClientMessage::PasswordLogin {
+                        username: self.username.clone(),
+                        password: self.password.clone(),
+                    })
+                }
+                LoginMode::Register
+                    if !self.username.is_empty()
+                        && !self.password.is_empty()
+                        && !self.invite_code.is_empty() =>
+                {
+                    Some(ClientMessage::Register {
+                        username: self.username.clone(),
+                        password: self.password.clone(),
+                        invite_code: self.invite_code.clone(),
+                    })
+                }
+                _ => None,
+            };
+
+            if let Some(message) = message {
+                connection_state.send_message(message);
             }
         }
     }
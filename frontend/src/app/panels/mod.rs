@@ -1,5 +1,6 @@
 pub mod challenge_list;
 pub mod challenge_panel;
+pub mod cheat_suspicions;
 pub mod connecting;
 pub mod hacker_list;
 pub mod login;
@@ -1,3 +1,4 @@
+use common::ctf_message::EventSchedule;
 use eframe::egui;
 use egui::{
     plot::{Corner, Legend, Line, MarkerShape, Plot, PlotBounds, Points},
@@ -5,6 +6,7 @@ use egui::{
 };
 
 use itertools::Itertools;
+use wasm_timer::SystemTime;
 
 use crate::app::ClientState;
 
@@ -18,33 +20,52 @@ impl ScoreboardPanel {
 
     pub fn ui(&mut self, ui: &mut egui::Ui, ctf_state: &ClientState) {
         if let Some(global_state) = &ctf_state.ctf_state.global_data {
-            // Store the lowest time solve. The CTF will "Start 20 minutes
-            // before that" for now. Later, we can add a "Start at" field to the
-            // CTF in the database.
-            // TODO: this ^
-            let lowest_time = global_state
-                .scoreboard
-                .teams
-                .values()
-                .filter_map(|solves| solves.iter().map(|s| s.time_millis).min())
-                .min()
-                .unwrap_or(0);
-
-            // The latest time a solve was submitted
-            let highest_time = global_state
+            let now = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+                .as_millis();
+
+            // Show how long until the CTF starts or ends
+            if let Some(event) = &global_state.event {
+                ui.heading(event_status(event, now));
+                ui.ctx()
+                    .request_repaint_after(std::time::Duration::from_secs(1));
+            }
+
+            // The scoreboard starts at the start of the CTF. If there isn't a
+            // schedule, fall back to the earliest solve.
+            let lowest_time = match &global_state.event {
+                Some(event) => event.start_millis,
+                None => global_state
+                    .scoreboard
+                    .teams
+                    .values()
+                    .filter_map(|solves| solves.iter().map(|s| s.time_millis).min())
+                    .min()
+                    .unwrap_or(0),
+            };
+
+            // The latest time a solve was submitted. If there is a schedule,
+            // show up until now, or the end of the CTF if it's over.
+            let latest_solve = global_state
                 .scoreboard
                 .teams
                 .values()
                 .filter_map(|solves| solves.iter().map(|s| s.time_millis).max())
                 .max()
                 .unwrap_or(0);
+            let highest_time = match &global_state.event {
+                Some(event) => now.clamp(event.start_millis, event.end_millis),
+                None => latest_solve,
+            }
+            .max(lowest_time);
 
             // The team with the max score
             let max_team_score = global_state
                 .scoreboard
                 .teams
                 .values()
-                .map(|solves| solves.iter().map(|s| s.points).sum::<u32>())
+                .map(|solves| solves.iter().map(|s| s.points).sum::<i32>())
                 .max()
                 .unwrap_or(0);
 
@@ -101,7 +122,9 @@ impl ScoreboardPanel {
                                 .fold((0, vec![[0.0, 0.0]]), |mut acc, s| {
                                     acc.0 += s.points;
                                     acc.1.push([
-                                        (s.time_millis - lowest_time) as f64 / 1000.0 / 60.0,
+                                        s.time_millis.saturating_sub(lowest_time) as f64
+                                            / 1000.0
+                                            / 60.0,
                                         acc.0 as f64,
                                     ]);
                                     acc
@@ -118,7 +141,9 @@ impl ScoreboardPanel {
                                     .fold((0, vec![[0.0, 0.0]]), |mut acc, s| {
                                         acc.0 += s.points;
                                         acc.1.push([
-                                            (s.time_millis - lowest_time) as f64 / 1000.0 / 60.0,
+                                            s.time_millis.saturating_sub(lowest_time) as f64
+                                                / 1000.0
+                                                / 60.0,
                                             acc.0 as f64,
                                         ]);
                                         acc
@@ -130,6 +155,32 @@ impl ScoreboardPanel {
                             .radius(3.0)
                             .shape(MarkerShape::Circle),
                         );
+
+                        // Mark the solves that were first blood
+                        plot_ui.points(
+                            Points::new(
+                                solves
+                                    .iter()
+                                    .sorted_by(|a, b| a.time_millis.cmp(&b.time_millis))
+                                    .fold((0, vec![]), |mut acc, s| {
+                                        acc.0 += s.points;
+                                        if s.blood == Some(1) {
+                                            acc.1.push([
+                                                s.time_millis.saturating_sub(lowest_time) as f64
+                                                    / 1000.0
+                                                    / 60.0,
+                                                acc.0 as f64,
+                                            ]);
+                                        }
+                                        acc
+                                    })
+                                    .1,
+                            )
+                            .name(team_name)
+                            .filled(true)
+                            .radius(6.0)
+                            .shape(MarkerShape::Diamond),
+                        );
                     }
 
                     let time_diff = (highest_time - lowest_time) as f64 / 1000.0 / 60.0;
@@ -162,3 +213,33 @@ impl ScoreboardPanel {
         }
     }
 }
+
+/// A line describing where we are in the CTF, such as "Starts in 01:02:03"
+fn event_status(event: &EventSchedule, now: u128) -> String {
+    if now < event.start_millis {
+        format!("Starts in {}", format_countdown(event.start_millis - now))
+    } else if now < event.end_millis {
+        let frozen = match event.freeze_millis {
+            Some(freeze) if now >= freeze => " (scoreboard frozen)",
+            _ => "",
+        };
+        format!(
+            "Ends in {}{}",
+            format_countdown(event.end_millis - now),
+            frozen
+        )
+    } else {
+        "The CTF is over".to_string()
+    }
+}
+
+/// Format milliseconds as hours, minutes and seconds
+fn format_countdown(millis: u128) -> String {
+    let seconds = millis / 1000;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
+}
@@ -1,7 +1,4 @@
-use common::{
-    ctf_message::{CTFMessage, TeamData},
-    NetworkMessage,
-};
+use common::ctf_message::{ClientMessage, TeamData};
 use eframe::egui;
 use egui::Align2;
 use egui_extras::{Column, TableBuilder};
@@ -68,8 +65,8 @@ impl TeamPanel {
                 if ui.button("Join team").clicked() {
                     // Send the submission to the server if it's not empty
                     if !self.team_join_token_field.is_empty() {
-                        connection_state.send_message(NetworkMessage::CTFMessage(
-                            CTFMessage::JoinTeam(self.team_join_token_field.clone()),
+                        connection_state.send_message(ClientMessage::JoinTeam(
+                            self.team_join_token_field.clone(),
                         ));
                     }
                 }
@@ -88,20 +85,22 @@ impl TeamPanel {
                 if ui.button("Create team").clicked() {
                     // Send the submission to the server if it's not empty
                     if !self.team_create_team_name_field.is_empty() {
-                        connection_state.send_message(NetworkMessage::CTFMessage(
-                            CTFMessage::CreateTeam(self.team_create_team_name_field.clone()),
+                        connection_state.send_message(ClientMessage::CreateTeam(
+                            self.team_create_team_name_field.clone(),
                         ));
                     }
                 }
             }
-            TeamData::OnTeam(hacker_team) => {
+            TeamData::OnTeam {
+                team: hacker_team,
+                is_captain,
+            } => {
                 ui.heading(&hacker_team.name);
 
                 // Leave team button
                 if ui.button("Leave team").clicked() {
                     // TODO: Leave team
-                    connection_state
-                        .send_message(NetworkMessage::CTFMessage(CTFMessage::LeaveTeam));
+                    connection_state.send_message(ClientMessage::LeaveTeam);
                 }
 
                 ui.separator();
@@ -120,10 +119,23 @@ impl TeamPanel {
                     })
                 };
 
-                // Copy join token button
-                if ui.button("Copy to clipboard").clicked() {
-                    ui.output_mut(|o| o.copied_text = hacker_team.join_token.clone());
-                }
+                ui.horizontal(|ui| {
+                    // Copy join token button
+                    if ui.button("Copy to clipboard").clicked() {
+                        ui.output_mut(|o| o.copied_text = hacker_team.join_token.clone());
+                    }
+
+                    // Only the captain can make a new one, say if the old one
+                    // got shared with the wrong people
+                    if *is_captain
+                        && ui
+                            .button("Regenerate")
+                            .on_hover_text("The old token will stop working")
+                            .clicked()
+                    {
+                        connection_state.send_message(ClientMessage::RegenerateJoinToken);
+                    }
+                });
 
                 ui.separator();
 
@@ -132,9 +144,7 @@ impl TeamPanel {
                 ui.add_enabled_ui(self.enabled, |ui| {
                     let table = TableBuilder::new(ui)
                         .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
-                        .column(Column::auto())
-                        .column(Column::initial(100.0).range(40.0..=300.0))
-                        .column(Column::initial(100.0).at_least(40.0).clip(true))
+                        .column(Column::initial(150.0).range(40.0..=300.0))
                         .column(Column::remainder())
                         .min_scrolled_height(0.0);
 
@@ -145,23 +155,38 @@ impl TeamPanel {
                                 ui.strong("Member");
                             });
                             header.col(|ui| {
-                                ui.strong("Challenge");
-                            });
-                            header.col(|ui| {
-                                ui.strong("Status");
+                                ui.strong("Role");
                             });
                         })
                         .body(|mut body| {
-                            for hacker in ["hacker1", "hacker2", "hacker3"] {
+                            for hacker in &hacker_team.hackers {
+                                let captain = hacker_team.captain.as_ref() == Some(&hacker.name);
+
                                 body.row(20.0, |mut row| {
                                     row.col(|ui| {
-                                        ui.label(hacker);
-                                    });
-                                    row.col(|ui| {
-                                        ui.label("test");
+                                        ui.label(&hacker.name);
                                     });
                                     row.col(|ui| {
-                                        ui.label("test");
+                                        if captain {
+                                            ui.label("Captain");
+                                        } else if *is_captain {
+                                            // The captain can manage everyone
+                                            // else on the team
+                                            if ui.button("Make captain").clicked() {
+                                                connection_state.send_message(
+                                                    ClientMessage::TransferCaptain(
+                                                        hacker.name.clone(),
+                                                    ),
+                                                );
+                                            }
+                                            if ui.button("Kick").clicked() {
+                                                connection_state.send_message(
+                                                    ClientMessage::KickMember(hacker.name.clone()),
+                                                );
+                                            }
+                                        } else {
+                                            ui.label("Member");
+                                        }
                                     });
                                 });
                             }
@@ -6,6 +6,23 @@ mod m20230620_143225_challenge;
 mod m20230620_143309_submission;
 mod m20230622_165808_login_token;
 mod m20230710_212225_message_component_data;
+mod m20230715_130000_hacker_role;
+mod m20230715_131500_audit_log;
+mod m20230716_101500_event;
+mod m20230717_094500_challenge_scoring;
+mod m20230718_160000_submission_blood;
+mod m20230719_113000_challenge_flag;
+mod m20230720_141500_cheat_suspicion;
+mod m20230721_090000_challenge_requirement;
+mod m20230722_101500_hint;
+mod m20230723_134500_challenge_file;
+mod m20230724_093000_challenge_retired;
+mod m20230725_100000_token_kind;
+mod m20230726_094500_local_accounts;
+mod m20230727_101500_oauth_identity;
+mod m20230728_093000_team_captain;
+mod m20230729_091500_hacker_username_unique;
+mod m20230730_094500_invite_code_role;
 
 pub struct Migrator;
 
@@ -19,6 +36,23 @@ impl MigratorTrait for Migrator {
             Box::new(m20230620_143309_submission::Migration),
             Box::new(m20230622_165808_login_token::Migration),
             Box::new(m20230710_212225_message_component_data::Migration),
+            Box::new(m20230715_130000_hacker_role::Migration),
+            Box::new(m20230715_131500_audit_log::Migration),
+            Box::new(m20230716_101500_event::Migration),
+            Box::new(m20230717_094500_challenge_scoring::Migration),
+            Box::new(m20230718_160000_submission_blood::Migration),
+            Box::new(m20230719_113000_challenge_flag::Migration),
+            Box::new(m20230720_141500_cheat_suspicion::Migration),
+            Box::new(m20230721_090000_challenge_requirement::Migration),
+            Box::new(m20230722_101500_hint::Migration),
+            Box::new(m20230723_134500_challenge_file::Migration),
+            Box::new(m20230724_093000_challenge_retired::Migration),
+            Box::new(m20230725_100000_token_kind::Migration),
+            Box::new(m20230726_094500_local_accounts::Migration),
+            Box::new(m20230727_101500_oauth_identity::Migration),
+            Box::new(m20230728_093000_team_captain::Migration),
+            Box::new(m20230729_091500_hacker_username_unique::Migration),
+            Box::new(m20230730_094500_invite_code_role::Migration),
         ]
     }
 }
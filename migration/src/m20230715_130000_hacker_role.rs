@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Hacker {
+    Table,
+    Role,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Everyone that already exists is a regular hacker
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Hacker::Table)
+                    .add_column(
+                        ColumnDef::new(Hacker::Role)
+                            .string_len(16)
+                            .not_null()
+                            .default("hacker"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Hacker::Table)
+                    .drop_column(Hacker::Role)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,57 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum AuditLog {
+    Table,
+    Id,
+    Time,
+    Action,
+    Allowed,
+    FkHackerId,
+}
+
+#[derive(Iden)]
+enum Hacker {
+    Table,
+    DiscordId,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditLog::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditLog::Time).date_time().not_null())
+                    .col(ColumnDef::new(AuditLog::Action).string().not_null())
+                    .col(ColumnDef::new(AuditLog::Allowed).boolean().not_null())
+                    .col(ColumnDef::new(AuditLog::FkHackerId).big_integer().null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("audit_log_hacker_fk")
+                            .from(AuditLog::Table, AuditLog::FkHackerId)
+                            .to(Hacker::Table, Hacker::DiscordId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditLog::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Challenge {
+    Table,
+    ScoringMode,
+    MinimumPoints,
+    Decay,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing challenges keep their fixed point values
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Challenge::Table)
+                    .add_column(
+                        ColumnDef::new(Challenge::ScoringMode)
+                            .string_len(16)
+                            .not_null()
+                            .default("static"),
+                    )
+                    .add_column(ColumnDef::new(Challenge::MinimumPoints).integer().null())
+                    .add_column(ColumnDef::new(Challenge::Decay).integer().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Challenge::Table)
+                    .drop_column(Challenge::ScoringMode)
+                    .drop_column(Challenge::MinimumPoints)
+                    .drop_column(Challenge::Decay)
+                    .to_owned(),
+            )
+            .await
+    }
+}
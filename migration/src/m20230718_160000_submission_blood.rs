@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Submission {
+    Table,
+    Blood,
+    BonusPoints,
+    BloodAnnounced,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Which place a correct submission came in on its challenge (1 for
+        // first blood, up to 3), and the bonus it was given for it
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Submission::Table)
+                    .add_column(ColumnDef::new(Submission::Blood).integer().null())
+                    .add_column(
+                        ColumnDef::new(Submission::BonusPoints)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column(
+                        ColumnDef::new(Submission::BloodAnnounced)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Submission::Table)
+                    .drop_column(Submission::Blood)
+                    .drop_column(Submission::BonusPoints)
+                    .drop_column(Submission::BloodAnnounced)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,117 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum ChallengeFlag {
+    Table,
+    Id,
+    Flag,
+    MatchMode,
+    FkChallengeId,
+}
+
+#[derive(Iden)]
+enum Challenge {
+    Table,
+    Id,
+    Flag,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ChallengeFlag::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ChallengeFlag::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ChallengeFlag::Flag).string().not_null())
+                    .col(
+                        ColumnDef::new(ChallengeFlag::MatchMode)
+                            .string_len(16)
+                            .not_null()
+                            .default("exact"),
+                    )
+                    .col(
+                        ColumnDef::new(ChallengeFlag::FkChallengeId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("challenge_flag_challenge_fk")
+                            .from(ChallengeFlag::Table, ChallengeFlag::FkChallengeId)
+                            .to(Challenge::Table, Challenge::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every existing challenge keeps its flag as an exact match
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(ChallengeFlag::Table)
+                    .columns([ChallengeFlag::FkChallengeId, ChallengeFlag::Flag])
+                    .select_from(
+                        Query::select()
+                            .columns([Challenge::Id, Challenge::Flag])
+                            .from(Challenge::Table)
+                            .to_owned(),
+                    )
+                    .map_err(|e| DbErr::Migration(e.to_string()))?
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Challenge::Table)
+                    .drop_column(Challenge::Flag)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Challenge::Table)
+                    .add_column(
+                        ColumnDef::new(Challenge::Flag)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Only one flag fits back in the challenge table, so keep the first
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE challenge SET flag = challenge_flag.flag \
+                 FROM (SELECT DISTINCT ON (fk_challenge_id) fk_challenge_id, flag \
+                       FROM challenge_flag ORDER BY fk_challenge_id, id) AS challenge_flag \
+                 WHERE challenge.id = challenge_flag.fk_challenge_id",
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ChallengeFlag::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,130 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum CheatSuspicion {
+    Table,
+    Id,
+    Time,
+    Reason,
+    Flag,
+    FkChallengeId,
+    FkTeamId,
+    FkHackerId,
+    FkSourceTeamId,
+    FkSourceHackerId,
+}
+
+#[derive(Iden)]
+enum Challenge {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Team {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Hacker {
+    Table,
+    DiscordId,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CheatSuspicion::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(CheatSuspicion::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(CheatSuspicion::Time).date_time().not_null())
+                    .col(
+                        ColumnDef::new(CheatSuspicion::Reason)
+                            .string_len(32)
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(CheatSuspicion::Flag).string().not_null())
+                    .col(
+                        ColumnDef::new(CheatSuspicion::FkChallengeId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CheatSuspicion::FkTeamId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CheatSuspicion::FkHackerId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CheatSuspicion::FkSourceTeamId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(CheatSuspicion::FkSourceHackerId)
+                            .big_integer()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("cheat_suspicion_challenge_fk")
+                            .from(CheatSuspicion::Table, CheatSuspicion::FkChallengeId)
+                            .to(Challenge::Table, Challenge::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("cheat_suspicion_team_fk")
+                            .from(CheatSuspicion::Table, CheatSuspicion::FkTeamId)
+                            .to(Team::Table, Team::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("cheat_suspicion_hacker_fk")
+                            .from(CheatSuspicion::Table, CheatSuspicion::FkHackerId)
+                            .to(Hacker::Table, Hacker::DiscordId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("cheat_suspicion_source_team_fk")
+                            .from(CheatSuspicion::Table, CheatSuspicion::FkSourceTeamId)
+                            .to(Team::Table, Team::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("cheat_suspicion_source_hacker_fk")
+                            .from(CheatSuspicion::Table, CheatSuspicion::FkSourceHackerId)
+                            .to(Hacker::Table, Hacker::DiscordId)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CheatSuspicion::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Something a team has to do before a challenge unlocks. Either solving
+/// another challenge, or scoring enough points in a category.
+#[derive(Iden)]
+enum ChallengeRequirement {
+    Table,
+    Id,
+    FkChallengeId,
+    FkRequiredChallengeId,
+    Category,
+    Points,
+}
+
+#[derive(Iden)]
+enum Challenge {
+    Table,
+    Id,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ChallengeRequirement::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ChallengeRequirement::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ChallengeRequirement::FkChallengeId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ChallengeRequirement::FkRequiredChallengeId)
+                            .integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(ChallengeRequirement::Category)
+                            .string()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(ChallengeRequirement::Points)
+                            .integer()
+                            .null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("challenge_requirement_challenge_fk")
+                            .from(
+                                ChallengeRequirement::Table,
+                                ChallengeRequirement::FkChallengeId,
+                            )
+                            .to(Challenge::Table, Challenge::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("challenge_requirement_required_challenge_fk")
+                            .from(
+                                ChallengeRequirement::Table,
+                                ChallengeRequirement::FkRequiredChallengeId,
+                            )
+                            .to(Challenge::Table, Challenge::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ChallengeRequirement::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,143 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Hint {
+    Table,
+    Id,
+    FkChallengeId,
+    Position,
+    Text,
+    Cost,
+}
+
+/// A team paying for a hint
+#[derive(Iden)]
+enum HintUnlock {
+    Table,
+    Id,
+    Time,
+    FkHintId,
+    FkTeamId,
+    FkHackerId,
+}
+
+#[derive(Iden)]
+enum Challenge {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Team {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Hacker {
+    Table,
+    DiscordId,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Hint::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Hint::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Hint::FkChallengeId).integer().not_null())
+                    .col(ColumnDef::new(Hint::Position).integer().not_null())
+                    .col(ColumnDef::new(Hint::Text).string().not_null())
+                    .col(ColumnDef::new(Hint::Cost).integer().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("hint_challenge_fk")
+                            .from(Hint::Table, Hint::FkChallengeId)
+                            .to(Challenge::Table, Challenge::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(HintUnlock::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(HintUnlock::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(HintUnlock::Time).date_time().not_null())
+                    .col(ColumnDef::new(HintUnlock::FkHintId).integer().not_null())
+                    .col(ColumnDef::new(HintUnlock::FkTeamId).integer().not_null())
+                    .col(
+                        ColumnDef::new(HintUnlock::FkHackerId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("hint_unlock_hint_fk")
+                            .from(HintUnlock::Table, HintUnlock::FkHintId)
+                            .to(Hint::Table, Hint::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("hint_unlock_team_fk")
+                            .from(HintUnlock::Table, HintUnlock::FkTeamId)
+                            .to(Team::Table, Team::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("hint_unlock_hacker_fk")
+                            .from(HintUnlock::Table, HintUnlock::FkHackerId)
+                            .to(Hacker::Table, Hacker::DiscordId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A team only ever pays for a hint once
+        manager
+            .create_index(
+                Index::create()
+                    .name("hint_unlock_hint_team_idx")
+                    .table(HintUnlock::Table)
+                    .col(HintUnlock::FkHintId)
+                    .col(HintUnlock::FkTeamId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(HintUnlock::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Hint::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,101 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// The contents of a file, stored once no matter how many challenges use it
+#[derive(Iden)]
+enum Blob {
+    Table,
+    Hash,
+    Data,
+}
+
+#[derive(Iden)]
+enum ChallengeFile {
+    Table,
+    Id,
+    FkChallengeId,
+    Name,
+    Size,
+    FkBlobHash,
+}
+
+#[derive(Iden)]
+enum Challenge {
+    Table,
+    Id,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Blob::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Blob::Hash)
+                            .string_len(64)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Blob::Data).binary().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ChallengeFile::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ChallengeFile::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ChallengeFile::FkChallengeId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ChallengeFile::Name).string().not_null())
+                    .col(ColumnDef::new(ChallengeFile::Size).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(ChallengeFile::FkBlobHash)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("challenge_file_challenge_fk")
+                            .from(ChallengeFile::Table, ChallengeFile::FkChallengeId)
+                            .to(Challenge::Table, Challenge::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("challenge_file_blob_fk")
+                            .from(ChallengeFile::Table, ChallengeFile::FkBlobHash)
+                            .to(Blob::Table, Blob::Hash)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ChallengeFile::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Blob::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,74 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Challenge {
+    Table,
+    RepoPath,
+    Retired,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing challenges get their folder the next time the repo is
+        // loaded, matched up by title
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Challenge::Table)
+                    .add_column(ColumnDef::new(Challenge::RepoPath).string().null())
+                    .add_column(
+                        ColumnDef::new(Challenge::Retired)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("challenge_repo_path_idx")
+                    .table(Challenge::Table)
+                    .col(Challenge::RepoPath)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Retired challenges keep their title, so a new challenge has to be
+        // able to use it again
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE challenge DROP CONSTRAINT IF EXISTS challenge_title_key",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE challenge ADD CONSTRAINT challenge_title_key UNIQUE (title)",
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Challenge::Table)
+                    .drop_column(Challenge::RepoPath)
+                    .drop_column(Challenge::Retired)
+                    .to_owned(),
+            )
+            .await
+    }
+}
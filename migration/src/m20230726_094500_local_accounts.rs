@@ -0,0 +1,137 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Hacker {
+    Table,
+    Id,
+    DiscordId,
+    PasswordHash,
+}
+
+/// Lets someone register an account with a password, without going through
+/// Discord
+#[derive(Iden)]
+enum InviteCode {
+    Table,
+    Code,
+    UsesLeft,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Hackers get their own id, so they don't need a Discord account.
+        // Every foreign key on the old column follows it to the new name.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Hacker::Table)
+                    .rename_column(Hacker::DiscordId, Hacker::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        // New hackers are numbered by the database, after every id that came
+        // from Discord
+        manager
+            .get_connection()
+            .execute_unprepared("CREATE SEQUENCE hacker_id_seq OWNED BY hacker.id")
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE hacker ALTER COLUMN id SET DEFAULT nextval('hacker_id_seq')",
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "SELECT setval('hacker_id_seq', COALESCE(MAX(id), 0) + 1, false) FROM hacker",
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Hacker::Table)
+                    .add_column(
+                        ColumnDef::new(Hacker::DiscordId)
+                            .big_integer()
+                            .null()
+                            .unique_key(),
+                    )
+                    .add_column(ColumnDef::new(Hacker::PasswordHash).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every hacker so far came from Discord, and their id was their
+        // Discord id
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Hacker::Table)
+                    .value(Hacker::DiscordId, Expr::col(Hacker::Id))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(InviteCode::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(InviteCode::Code)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(InviteCode::UsesLeft).integer().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(InviteCode::Table).to_owned())
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared("ALTER TABLE hacker ALTER COLUMN id DROP DEFAULT")
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared("DROP SEQUENCE hacker_id_seq")
+            .await?;
+
+        // Hackers made after this keep their own id, so only hackers from
+        // before it can log in with Discord again
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Hacker::Table)
+                    .drop_column(Hacker::DiscordId)
+                    .drop_column(Hacker::PasswordHash)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Hacker::Table)
+                    .rename_column(Hacker::Id, Hacker::DiscordId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
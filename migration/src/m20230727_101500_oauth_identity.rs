@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Who a hacker is to an OAuth provider
+#[derive(Iden)]
+enum OauthIdentity {
+    Table,
+    Provider,
+    Subject,
+    FkHackerId,
+}
+
+#[derive(Iden)]
+enum Hacker {
+    Table,
+    Id,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(OauthIdentity::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(OauthIdentity::Provider).string().not_null())
+                    .col(ColumnDef::new(OauthIdentity::Subject).string().not_null())
+                    .col(
+                        ColumnDef::new(OauthIdentity::FkHackerId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(OauthIdentity::Provider)
+                            .col(OauthIdentity::Subject),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("oauth_identity_hacker_fk")
+                            .from(OauthIdentity::Table, OauthIdentity::FkHackerId)
+                            .to(Hacker::Table, Hacker::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OauthIdentity::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Team {
+    Table,
+    FkCaptainId,
+}
+
+#[derive(Iden)]
+enum Hacker {
+    Table,
+    Id,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Team::Table)
+                    .add_column(ColumnDef::new(Team::FkCaptainId).big_integer().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("team_captain_fk")
+                            .from_tbl(Team::Table)
+                            .from_col(Team::FkCaptainId)
+                            .to_tbl(Hacker::Table)
+                            .to_col(Hacker::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Nobody knows who made the teams that are already there, so their
+        // first member is as good a captain as any
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE team SET fk_captain_id = \
+                 (SELECT MIN(id) FROM hacker WHERE hacker.fk_team_id = team.id)",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Team::Table)
+                    .drop_foreign_key(Alias::new("team_captain_fk"))
+                    .drop_column(Team::FkCaptainId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
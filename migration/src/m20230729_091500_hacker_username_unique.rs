@@ -0,0 +1,48 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum Hacker {
+    Table,
+    Username,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Names were only checked before they were taken, so two hackers
+        // might already share one. Whoever had it first keeps it.
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE hacker SET username = username || '-' || id \
+                 WHERE id NOT IN (SELECT MIN(id) FROM hacker GROUP BY username)",
+            )
+            .await?;
+
+        // Nobody can have the same name as someone else on the scoreboard
+        manager
+            .create_index(
+                Index::create()
+                    .name("hacker_username_idx")
+                    .table(Hacker::Table)
+                    .col(Hacker::Username)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("hacker_username_idx")
+                    .table(Hacker::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
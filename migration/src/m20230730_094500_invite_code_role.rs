@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[derive(Iden)]
+enum InviteCode {
+    Table,
+    Role,
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The role whoever registers with the code gets. Codes that already
+        // exist are synced from the config at startup.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(InviteCode::Table)
+                    .add_column(
+                        ColumnDef::new(InviteCode::Role)
+                            .string_len(16)
+                            .not_null()
+                            .default("hacker"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(InviteCode::Table)
+                    .drop_column(InviteCode::Role)
+                    .to_owned(),
+            )
+            .await
+    }
+}